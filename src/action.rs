@@ -1,22 +1,49 @@
+use std::sync::Arc;
+
 use crate::{
-    providers::AuthProperties,
-    store::{explorer::TreeItem, state::DashboardComponents},
+    providers::{redact_properties, AuthProperties},
+    store::{
+        conflicts::ConflictResolution,
+        explorer::TreeItem,
+        sources::traits::{DownloadSettings, FailedDownloads},
+        state::DashboardComponents,
+        sync::{SyncLocation, SyncPlan, SyncRequest},
+    },
 };
 
 #[derive(Debug, Clone)]
 pub enum Action {
     Quit,
-    Tick,
-    Render,
-    Key(String),
     SetSource(usize),
     SetAccount(usize),
     SetExplorerFolder(TreeItem),
-    Download(Vec<TreeItem>),
+    Download(Vec<TreeItem>, DownloadSettings),
+    PreviewDownload(Vec<TreeItem>),
+    RetryDownloads(FailedDownloads),
+    PlanSync(SyncRequest),
+    RunSync(Arc<SyncPlan>),
+    CompareFolders(SyncLocation, SyncLocation),
+    CloseDiff,
     ChangeRegion(String),
+    #[allow(dead_code)]
     RefreshCredentials,
     EditCredentials(String, AuthProperties),
     SetSelectedComponent(DashboardComponents),
     CycleSelectedComponent,
     DismissLastAlert,
+    CancelTransfer(usize),
+    CancelAllTransfers,
+    ResolveConflict(usize, ConflictResolution),
+}
+
+impl Action {
+    // copy of the action that can be logged or stored, secret values are masked
+    pub fn redacted(&self) -> Self {
+        match self {
+            Action::EditCredentials(account, properties) => {
+                Action::EditCredentials(account.clone(), redact_properties(properties))
+            }
+            action => action.clone(),
+        }
+    }
 }
@@ -3,17 +3,8 @@ use std::{fs::OpenOptions, io::Write};
 use anyhow::{Ok, Result};
 use time::OffsetDateTime;
 
-pub trait LogToFile {
-    fn write_to_file(&self, message: &str) -> Result<()> {
-        LOGGER.write_to_file(message)
-    }
-    fn info(&self, message: &str) -> Result<()> {
-        self.write_to_file(message)
-    }
-}
-
 pub struct Logger<'a>(&'a str);
-impl<'a> Logger<'a> {
+impl Logger<'_> {
     pub fn write_to_file(&self, message: &str) -> Result<()> {
         let mut file = OpenOptions::new()
             .create(true)
@@ -26,9 +17,6 @@ impl<'a> Logger<'a> {
     pub fn info(&self, message: &str) -> Result<()> {
         self.write_to_file(message)
     }
-    pub fn change_file(&mut self, log_file: &'a str) {
-        self.0 = log_file;
-    }
 }
 
 pub static LOGGER: Logger = Logger("log.txt");
@@ -1,9 +1,6 @@
-use std::sync::Arc;
-
 use anyhow::{Ok, Result};
-use providers::AwsClient;
-use store::state::State;
-use tokio::sync::Mutex;
+use providers::{AwsClient, SharedClient};
+use store::{preferences::Preferences, state::State};
 use tui::ui::Ui;
 mod action;
 mod logger;
@@ -14,9 +11,14 @@ mod tui;
 #[tokio::main]
 async fn main() -> Result<()> {
     let (ui, ui_rx) = Ui::new();
-    let client = Arc::new(Mutex::new(AwsClient::new().await));
+    let preferences = Preferences::load();
+    let client = SharedClient::new(
+        AwsClient::new()
+            .await
+            .with_download_options(preferences.download_options.clone()),
+    );
 
-    let (mut state, state_rx) = State::new(client.clone()).await?;
+    let (mut state, state_rx) = State::new(client.clone(), preferences).await?;
 
     let _result = tokio::try_join!(ui.start(state_rx), state.start(ui_rx));
 
@@ -1,6 +1,13 @@
 mod aws;
+pub use aws::matches_etag;
 pub use aws::AccountMap;
 pub use aws::AuthProperties;
 pub use aws::AwsClient;
+pub use aws::DownloadOptions;
+pub use aws::SharedClient;
+pub use aws::{filter_regions, is_known_region, validate_region};
+pub use aws::{is_secret_property, redact_properties, REDACTED_VALUE};
+mod partial_download;
+pub use partial_download::PartialDownload;
 mod traits;
-pub use traits::ProviderClient;
+pub use traits::{ProviderClient, RemoteFile, RemoteFileStream};
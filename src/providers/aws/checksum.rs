@@ -0,0 +1,169 @@
+use std::{fs::File, io::Read, path::Path};
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use md5::{Digest, Md5};
+use sha2::Sha256;
+
+const READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+// checksum that a downloaded file can be verified against, values are kept in
+// the encoding s3 uses: base64 for the additional checksums, hex for the etag
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpectedChecksum {
+    Crc32(String),
+    Crc32c(String),
+    Sha256(String),
+    Md5(String),
+}
+
+// checksums of multipart uploads are computed over the checksums of the parts,
+// they end with -<number of parts> and can't be compared with the whole file
+fn is_full_object_checksum(checksum: &&str) -> bool {
+    !checksum.is_empty() && !checksum.contains('-')
+}
+
+impl ExpectedChecksum {
+    pub fn pick(
+        crc32: Option<&str>,
+        crc32c: Option<&str>,
+        sha256: Option<&str>,
+        etag: Option<&str>,
+    ) -> Option<Self> {
+        if let Some(sha256) = sha256.filter(is_full_object_checksum) {
+            return Some(Self::Sha256(sha256.to_string()));
+        }
+        if let Some(crc32c) = crc32c.filter(is_full_object_checksum) {
+            return Some(Self::Crc32c(crc32c.to_string()));
+        }
+        if let Some(crc32) = crc32.filter(is_full_object_checksum) {
+            return Some(Self::Crc32(crc32.to_string()));
+        }
+        // the etag is the md5 of the content only for objects uploaded in a single part
+        let etag = etag?.trim_matches('"');
+        if etag.len() == 32 && etag.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Some(Self::Md5(etag.to_lowercase()));
+        }
+        None
+    }
+
+    fn compute(&self, file: &Path) -> Result<String> {
+        let mut file = File::open(file)?;
+        let mut buffer = vec![0; READ_BUFFER_SIZE];
+        match self {
+            Self::Crc32(_) => {
+                let mut hasher = crc32fast::Hasher::new();
+                read_chunks(&mut file, &mut buffer, |chunk| hasher.update(chunk))?;
+                Ok(STANDARD.encode(hasher.finalize().to_be_bytes()))
+            }
+            Self::Crc32c(_) => {
+                let mut crc = 0;
+                read_chunks(&mut file, &mut buffer, |chunk| {
+                    crc = crc32c::crc32c_append(crc, chunk)
+                })?;
+                Ok(STANDARD.encode(crc.to_be_bytes()))
+            }
+            Self::Sha256(_) => {
+                let mut hasher = Sha256::new();
+                read_chunks(&mut file, &mut buffer, |chunk| hasher.update(chunk))?;
+                Ok(STANDARD.encode(hasher.finalize()))
+            }
+            Self::Md5(_) => {
+                let mut hasher = Md5::new();
+                read_chunks(&mut file, &mut buffer, |chunk| hasher.update(chunk))?;
+                Ok(hex::encode(hasher.finalize()))
+            }
+        }
+    }
+
+    pub fn verify(&self, file: &Path) -> Result<()> {
+        let (algorithm, expected) = match self {
+            Self::Crc32(expected) => ("CRC32", expected),
+            Self::Crc32c(expected) => ("CRC32C", expected),
+            Self::Sha256(expected) => ("SHA256", expected),
+            Self::Md5(expected) => ("MD5", expected),
+        };
+        let actual = self.compute(file)?;
+        if actual != *expected {
+            return Err(anyhow!(
+                "{algorithm} checksum mismatch, expected {expected} but got {actual}"
+            ));
+        }
+        Ok(())
+    }
+}
+
+// whether a local file has the content of an object, None when the etag is not
+// the md5 of the content
+pub fn matches_etag(file: &Path, etag: &str) -> Result<Option<bool>> {
+    match ExpectedChecksum::pick(None, None, None, Some(etag)) {
+        Some(expected @ ExpectedChecksum::Md5(_)) => Ok(Some(
+            ExpectedChecksum::Md5(expected.compute(file)?) == expected,
+        )),
+        _ => Ok(None),
+    }
+}
+
+fn read_chunks(file: &mut File, buffer: &mut [u8], mut on_chunk: impl FnMut(&[u8])) -> Result<()> {
+    loop {
+        let read = file.read(buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        on_chunk(&buffer[..read]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn picks_only_verifiable_checksums() {
+        assert_eq!(
+            ExpectedChecksum::pick(Some("abc="), None, None, Some("\"etag\"")),
+            Some(ExpectedChecksum::Crc32("abc=".to_string()))
+        );
+        assert_eq!(
+            ExpectedChecksum::pick(
+                Some("abc=-3"),
+                None,
+                None,
+                Some("\"5EB63BBBE01EEED093CB22BB8F5ACDC3\"")
+            ),
+            Some(ExpectedChecksum::Md5(
+                "5eb63bbbe01eeed093cb22bb8f5acdc3".to_string()
+            ))
+        );
+        assert_eq!(
+            ExpectedChecksum::pick(
+                None,
+                None,
+                None,
+                Some("\"0a1b2c3d4e5f60718293a4b5c6d7e8f9-2\"")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn verifies_file_content() {
+        let file = std::env::temp_dir().join(format!("s3li-checksum-{}", std::process::id()));
+        fs::write(&file, "hello world").unwrap();
+
+        let checksums = [
+            ExpectedChecksum::Crc32("DUoRhQ==".to_string()),
+            ExpectedChecksum::Crc32c("yZRlqg==".to_string()),
+            ExpectedChecksum::Sha256("uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek=".to_string()),
+            ExpectedChecksum::Md5("5eb63bbbe01eeed093cb22bb8f5acdc3".to_string()),
+        ];
+        for checksum in checksums {
+            assert!(checksum.verify(&file).is_ok(), "{checksum:?}");
+        }
+        assert!(ExpectedChecksum::Md5("0".repeat(32)).verify(&file).is_err());
+
+        fs::remove_file(&file).unwrap();
+    }
+}
@@ -1,22 +1,61 @@
 use std::{
-    collections::HashMap,
-    fs::{self, OpenOptions},
+    collections::{HashMap, HashSet},
+    fs,
     io::Write,
+    sync::{Arc, Mutex, PoisonError},
+    time::SystemTime,
 };
 
 use anyhow::{anyhow, Result};
-use aws_config::{profile::ProfileFileCredentialsProvider, BehaviorVersion, Region};
-use aws_sdk_s3::Client;
+use aws_config::{profile::ProfileFileCredentialsProvider, BehaviorVersion, Region, SdkConfig};
+use aws_sdk_s3::{
+    error::SdkError,
+    operation::{get_object::GetObjectOutput, head_object::HeadObjectOutput},
+    primitives::ByteStream,
+    types::ChecksumMode,
+    Client,
+};
+use futures::{stream, StreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+use crate::{
+    logger::LOGGER,
+    providers::{traits::ProviderClient, PartialDownload, RemoteFile, RemoteFileStream},
+};
+
+use super::{
+    checksum::ExpectedChecksum,
+    multipart::{self, MultipartUpload},
+    ranged::{DownloadOptions, RangedDownload},
+    retry::with_retries,
+    AuthProperties, Credentials,
+};
 
-use crate::{logger::LOGGER, providers::traits::ProviderClient};
+// characters left as is in the source of a copy, the slashes of the key included
+const COPY_SOURCE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
 
-use super::{AuthProperties, Credentials};
+// used when no region is configured for the profile
+const DEFAULT_REGION: &str = "eu-central-1";
 
+// regions are looked up the first time a bucket is used, None when the lookup failed
+type BucketRegions = Arc<Mutex<HashMap<String, Option<String>>>>;
+
+// the caches are shared by the snapshots of the client, they are replaced when
+// the account or the credentials of the config change
 #[derive(Debug, Clone)]
 pub struct AwsClient {
     account: String,
     client: Client,
+    config: SdkConfig,
+    regional_clients: Arc<Mutex<HashMap<String, Client>>>,
+    bucket_regions: BucketRegions,
     credentials: Credentials,
+    download_options: DownloadOptions,
     pub region: String,
 }
 
@@ -26,11 +65,26 @@ impl AwsClient {
     pub async fn new() -> Self {
         let config = aws_config::load_defaults(BehaviorVersion::v2024_03_28()).await;
         let client = Client::new(&config);
+        let region = config
+            .region()
+            .map(|region| region.to_string())
+            .unwrap_or_else(|| DEFAULT_REGION.to_string());
         Self {
             account: "default".to_string(),
-            region: "eu-central-1".to_string(),
+            region,
             credentials: Credentials::default(),
             client,
+            config,
+            regional_clients: Arc::default(),
+            bucket_regions: Arc::default(),
+            download_options: DownloadOptions::default(),
+        }
+    }
+
+    pub fn with_download_options(self, download_options: DownloadOptions) -> Self {
+        Self {
+            download_options,
+            ..self
         }
     }
 
@@ -44,12 +98,351 @@ impl AwsClient {
             .load()
             .await;
         self.client = Client::new(&config);
+        self.config = config;
+        // regional clients share the credentials of the main config, they are
+        // built again from the new one when needed
+        self.regional_clients = Arc::default();
+    }
+
+    fn build_regional_client(&self, region: &str) -> Client {
+        let config = aws_sdk_s3::config::Builder::from(&self.config)
+            .region(Region::new(region.to_string()))
+            .build();
+        Client::from_conf(config)
+    }
+
+    // buckets living outside of the configured region need a client for their own region
+    async fn client_for_bucket(&self, bucket: &str) -> Client {
+        match self.bucket_region(bucket).await {
+            Some(region) if region != self.region => self
+                .regional_clients
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(region)
+                .or_insert_with_key(|region| self.build_regional_client(region))
+                .clone(),
+            _ => self.client.clone(),
+        }
+    }
+
+    // a failed lookup isn't tried again, the bucket goes through the main client
+    async fn bucket_region(&self, bucket: &str) -> Option<String> {
+        let known = self
+            .bucket_regions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(bucket)
+            .cloned();
+        if let Some(region) = known {
+            return region;
+        }
+        let region = self
+            .fetch_bucket_region(bucket)
+            .await
+            .inspect_err(|e| {
+                let _ = LOGGER.info(&format!("{:?}", e));
+            })
+            .ok();
+        self.bucket_regions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(bucket.to_string(), region.clone());
+        region
+    }
+
+    async fn finish_download(
+        &self,
+        partial_download: &PartialDownload,
+        expected_file: ExpectedFile,
+    ) -> Result<()> {
+        verify_download(partial_download, expected_file.checksum).await?;
+        if self.download_options.preserve_modified_time {
+            if let Some(last_modified) = expected_file.last_modified {
+                partial_download.set_modified(last_modified)?;
+            }
+        }
+        partial_download.finish()
+    }
+
+    // on_progress receives the bytes written so far and the size of the object when known
+    async fn download_once(
+        &self,
+        bucket: &str,
+        file_key: &str,
+        file_name: &str,
+        mut on_progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<bool> {
+        let parent_folder_to_create = file_name
+            .split('/')
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .skip(1)
+            .rev()
+            .collect::<Vec<_>>();
+        let parent_folder_to_create = parent_folder_to_create.join("/");
+        let _ = fs::create_dir_all(parent_folder_to_create);
+
+        let partial_download = PartialDownload::new(file_name);
+        let client = self.client_for_bucket(bucket).await;
+
+        if let Some(resume_point) = partial_download.ranged_resume_point() {
+            let unchanged = client
+                .head_object()
+                .bucket(bucket)
+                .key(file_key)
+                .if_match(&resume_point.etag)
+                .checksum_mode(ChecksumMode::Enabled)
+                .send()
+                .await;
+            match unchanged {
+                Ok(head) => {
+                    RangedDownload {
+                        client: &client,
+                        bucket,
+                        key: file_key,
+                        etag: &resume_point.etag,
+                        total_bytes: resume_point.total_bytes,
+                        part_size: resume_point.part_size,
+                        partial_download: &partial_download,
+                    }
+                    .download(
+                        None,
+                        &resume_point.completed,
+                        self.download_options.max_parallel_parts,
+                        &mut on_progress,
+                    )
+                    .await?;
+                    self.finish_download(&partial_download, ExpectedFile::from_head(&head))
+                        .await?;
+                    return Ok(true);
+                }
+                Err(e) if is_stale_range_error(&e) => {
+                    let _ = LOGGER.info(&format!(
+                        "cannot resume download of {file_key}, starting over"
+                    ));
+                    partial_download.discard();
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let resumed_object = match partial_download.resume_point() {
+            Some((etag, offset)) => {
+                // if-match makes s3 refuse the range when the object changed since
+                // the part was written
+                let response = client
+                    .get_object()
+                    .bucket(bucket)
+                    .key(file_key)
+                    .range(format!("bytes={offset}-"))
+                    .if_match(etag)
+                    .send()
+                    .await;
+                match response {
+                    Ok(object) => Some((object, offset)),
+                    Err(e) if is_stale_range_error(&e) => {
+                        let _ = LOGGER.info(&format!(
+                            "cannot resume download of {file_key}, starting over"
+                        ));
+                        None
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            None => None,
+        };
+        let (object, mut destination_file, offset, expected_file) = match resumed_object {
+            Some((object, offset)) => {
+                // checksums are not returned for ranges, they come from the whole object
+                // along with its date
+                let head = client
+                    .head_object()
+                    .bucket(bucket)
+                    .key(file_key)
+                    .checksum_mode(ChecksumMode::Enabled)
+                    .send()
+                    .await?;
+                (
+                    object,
+                    partial_download.resume()?,
+                    offset,
+                    ExpectedFile::from_head(&head),
+                )
+            }
+            None => {
+                let object = client
+                    .get_object()
+                    .bucket(bucket)
+                    .key(file_key)
+                    .checksum_mode(ChecksumMode::Enabled)
+                    .send()
+                    .await?;
+                let expected_file = ExpectedFile::from_get_object(&object);
+                let total_bytes = object
+                    .content_length()
+                    .and_then(|length| u64::try_from(length).ok());
+                // big objects keep streaming the first part from this response
+                // while the other parts are requested in parallel
+                let etag = object.e_tag().map(|etag| etag.to_string());
+                if let (Some(etag), Some(total_bytes)) = (&etag, total_bytes) {
+                    if total_bytes > self.download_options.ranged_threshold {
+                        let part_size = self.download_options.part_size;
+                        partial_download.start_ranged(etag, total_bytes, part_size)?;
+                        RangedDownload {
+                            client: &client,
+                            bucket,
+                            key: file_key,
+                            etag,
+                            total_bytes,
+                            part_size,
+                            partial_download: &partial_download,
+                        }
+                        .download(
+                            Some(object.body),
+                            &HashSet::new(),
+                            self.download_options.max_parallel_parts,
+                            &mut on_progress,
+                        )
+                        .await?;
+                        self.finish_download(&partial_download, expected_file)
+                            .await?;
+                        return Ok(true);
+                    }
+                }
+                let destination_file = partial_download.start(etag.as_deref())?;
+                (object, destination_file, 0, expected_file)
+            }
+        };
+
+        let total_bytes = object
+            .content_length()
+            .and_then(|length| u64::try_from(length).ok())
+            .map(|length| length + offset);
+        let mut bytes_done = offset;
+        on_progress(bytes_done, total_bytes);
+        let mut body = object.body;
+        while let Some(bytes) = body.try_next().await? {
+            destination_file.write_all(&bytes)?;
+            bytes_done += bytes.len() as u64;
+            on_progress(bytes_done, total_bytes);
+        }
+        destination_file.flush()?;
+        self.finish_download(&partial_download, expected_file)
+            .await?;
+        Ok(true)
+    }
+
+    async fn fetch_bucket_region(&self, bucket: &str) -> Result<String> {
+        match self
+            .client
+            .get_bucket_location()
+            .bucket(bucket)
+            .send()
+            .await
+        {
+            Ok(location) => Ok(location_constraint_to_region(
+                location.location_constraint().map(|val| val.as_str()),
+            )),
+            Err(e) => {
+                let _ = LOGGER.info(&format!(
+                    "get_bucket_location failed for {bucket}, falling back to head_bucket {:?}",
+                    e
+                ));
+                // s3 always answers with the x-amz-bucket-region header, even when
+                // the request is redirected or denied
+                match self.client.head_bucket().bucket(bucket).send().await {
+                    Ok(head) => head
+                        .bucket_region()
+                        .map(|region| region.to_string())
+                        .ok_or_else(|| anyhow!("Missing region for bucket {bucket}")),
+                    Err(e) => e
+                        .raw_response()
+                        .and_then(|response| response.headers().get("x-amz-bucket-region"))
+                        .map(|region| region.to_string())
+                        .ok_or_else(|| anyhow!("Failed to resolve region for bucket {bucket}")),
+                }
+            }
+        }
+    }
+}
+
+// what a downloaded file is verified against and stamped with before being
+// moved in place
+struct ExpectedFile {
+    checksum: Option<ExpectedChecksum>,
+    last_modified: Option<SystemTime>,
+}
+
+impl ExpectedFile {
+    fn from_get_object(object: &GetObjectOutput) -> Self {
+        Self {
+            checksum: ExpectedChecksum::pick(
+                object.checksum_crc32(),
+                object.checksum_crc32_c(),
+                object.checksum_sha256(),
+                object.e_tag(),
+            ),
+            last_modified: object
+                .last_modified()
+                .and_then(|last_modified| SystemTime::try_from(*last_modified).ok()),
+        }
+    }
+
+    fn from_head(head: &HeadObjectOutput) -> Self {
+        Self {
+            checksum: ExpectedChecksum::pick(
+                head.checksum_crc32(),
+                head.checksum_crc32_c(),
+                head.checksum_sha256(),
+                head.e_tag(),
+            ),
+            last_modified: head
+                .last_modified()
+                .and_then(|last_modified| SystemTime::try_from(*last_modified).ok()),
+        }
+    }
+}
+
+// a corrupted part is thrown away, resuming it would keep the wrong bytes
+async fn verify_download(
+    partial_download: &PartialDownload,
+    expected_checksum: Option<ExpectedChecksum>,
+) -> Result<()> {
+    let Some(expected_checksum) = expected_checksum else {
+        return Ok(());
+    };
+    let part = partial_download.part_path().to_path_buf();
+    let verification = tokio::task::spawn_blocking(move || expected_checksum.verify(&part)).await?;
+    if verification.is_err() {
+        partial_download.discard();
+    }
+    verification
+}
+
+// precondition failed when the object changed, range not satisfiable when the
+// part is already as long as the object
+fn is_stale_range_error<E>(error: &SdkError<E>) -> bool {
+    error
+        .raw_response()
+        .is_some_and(|response| matches!(response.status().as_u16(), 412 | 416))
+}
+
+fn location_constraint_to_region(location_constraint: Option<&str>) -> String {
+    match location_constraint {
+        // buckets in us-east-1 have no location constraint
+        None | Some("") => "us-east-1".to_string(),
+        // legacy value for buckets created in eu-west-1
+        Some("EU") => "eu-west-1".to_string(),
+        Some(region) => region.to_string(),
     }
 }
 
 impl ProviderClient for AwsClient {
     async fn switch_account(&mut self, new_account: &str) {
         self.account = new_account.to_string();
+        // buckets of the new account need to be resolved again
+        self.bucket_regions = Arc::default();
         self.refresh_client().await;
     }
     async fn change_region(&mut self, region: String) {
@@ -57,6 +450,15 @@ impl ProviderClient for AwsClient {
         self.refresh_client().await;
     }
 
+    fn bucket_regions(&self) -> HashMap<String, String> {
+        self.bucket_regions
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter_map(|(bucket, region)| Some((bucket.clone(), region.clone()?)))
+            .collect()
+    }
+
     async fn list_buckets(&self) -> Result<Vec<String>> {
         let resp = self.client.list_buckets().send().await.map_err(|e| {
             let _ = LOGGER.info(&format!("Error during list_buckets {:?}", e));
@@ -97,51 +499,215 @@ impl ProviderClient for AwsClient {
         }
     }
 
-    async fn download_file(&self, bucket: &str, file_key: &str, file_name: &str) -> Result<bool> {
-        let parent_folder_to_create = file_name
-            .split('/')
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .skip(1)
-            .rev()
-            .collect::<Vec<_>>();
-        let parent_folder_to_create = parent_folder_to_create.join("/");
-        let _ = fs::create_dir_all(parent_folder_to_create);
-        let mut destination_file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(file_name)?;
+    // transient failures are retried with a growing delay, the part written by the
+    // failed attempt is resumed when possible
+    async fn download_file(
+        &self,
+        bucket: &str,
+        file_key: &str,
+        file_name: &str,
+        on_progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<bool> {
+        // every attempt reports to the same progress
+        let on_progress = Mutex::new(on_progress);
+        with_retries(
+            self.download_options.max_attempts,
+            &format!("download {file_key}"),
+            || {
+                self.download_once(bucket, file_key, file_name, |bytes_done, total_bytes| {
+                    if let Ok(mut on_progress) = on_progress.lock() {
+                        on_progress(bytes_done, total_bytes);
+                    }
+                })
+            },
+        )
+        .await
+    }
+
+    async fn file_metadata(&self, bucket: &str, file_key: &str) -> Result<RemoteFile> {
+        let head = self
+            .client_for_bucket(bucket)
+            .await
+            .head_object()
+            .bucket(bucket)
+            .key(file_key)
+            .send()
+            .await?;
+        Ok(RemoteFile {
+            size: head
+                .content_length()
+                .and_then(|length| u64::try_from(length).ok())
+                .unwrap_or_default(),
+            last_modified: head
+                .last_modified()
+                .and_then(|last_modified| SystemTime::try_from(*last_modified).ok()),
+            etag: head.e_tag().map(str::to_string),
+        })
+    }
+
+    // files bigger than the ranged threshold are uploaded in parts, the others in a
+    // single request. transient failures are retried
+    async fn upload_file(
+        &self,
+        bucket: &str,
+        file_key: &str,
+        file_name: &str,
+        mut on_progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> Result<bool> {
+        let size = fs::metadata(file_name)?.len();
+        let client = self.client_for_bucket(bucket).await;
+        if size > self.download_options.ranged_threshold {
+            MultipartUpload {
+                client: &client,
+                bucket,
+                key: file_key,
+                total_bytes: size,
+                part_size: multipart::part_size(size, self.download_options.part_size),
+                max_attempts: self.download_options.max_attempts,
+            }
+            .upload_file(
+                file_name,
+                self.download_options.max_parallel_parts,
+                &mut on_progress,
+            )
+            .await?;
+            return Ok(true);
+        }
+        on_progress(0, Some(size));
+        with_retries(
+            self.download_options.max_attempts,
+            &format!("upload {file_key}"),
+            || async {
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(file_key)
+                    .body(ByteStream::from_path(file_name).await?)
+                    .send()
+                    .await?;
+                Ok(())
+            },
+        )
+        .await?;
+        on_progress(size, Some(size));
+        Ok(true)
+    }
+
+    // the object is copied by s3 without going through the client, the request is
+    // sent to the region of the destination bucket. objects too big for a single
+    // request are copied in parts
+    async fn copy_object(
+        &self,
+        source_bucket: &str,
+        source_key: &str,
+        bucket: &str,
+        file_key: &str,
+    ) -> Result<()> {
+        let copy_source =
+            utf8_percent_encode(&format!("{source_bucket}/{source_key}"), COPY_SOURCE).to_string();
+        let client = self.client_for_bucket(bucket).await;
+        let size = self.file_metadata(source_bucket, source_key).await?.size;
+        if size > multipart::MAX_SINGLE_COPY {
+            return MultipartUpload {
+                client: &client,
+                bucket,
+                key: file_key,
+                total_bytes: size,
+                part_size: multipart::part_size(size, self.download_options.part_size),
+                max_attempts: self.download_options.max_attempts,
+            }
+            .copy(&copy_source, self.download_options.max_parallel_parts)
+            .await;
+        }
+        with_retries(
+            self.download_options.max_attempts,
+            &format!("copy {source_key}"),
+            || async {
+                client
+                    .copy_object()
+                    .copy_source(&copy_source)
+                    .bucket(bucket)
+                    .key(file_key)
+                    .send()
+                    .await?;
+                Ok(())
+            },
+        )
+        .await
+    }
 
+    async fn delete_object(&self, bucket: &str, file_key: &str) -> Result<()> {
+        self.client_for_bucket(bucket)
+            .await
+            .delete_object()
+            .bucket(bucket)
+            .key(file_key)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    async fn open_file(&self, bucket: &str, file_key: &str) -> Result<RemoteFileStream> {
         let object = self
-            .client
+            .client_for_bucket(bucket)
+            .await
             .get_object()
             .bucket(bucket)
             .key(file_key)
             .send()
             .await?;
-        let bytes = object.body.collect().await?.into_bytes();
-        destination_file.write_all(&bytes)?;
-        Ok(true)
+        let file = RemoteFile {
+            size: object
+                .content_length()
+                .and_then(|length| u64::try_from(length).ok())
+                .unwrap_or_default(),
+            last_modified: object
+                .last_modified()
+                .and_then(|last_modified| SystemTime::try_from(*last_modified).ok()),
+            etag: object.e_tag().map(str::to_string),
+        };
+        let chunks = stream::unfold(Some(object.body), |body| async move {
+            let mut body = body?;
+            match body.try_next().await {
+                Ok(Some(bytes)) => Some((Ok(bytes), Some(body))),
+                Ok(None) => None,
+                // nothing can be read after an error
+                Err(e) => Some((Err(e.into()), None)),
+            }
+        })
+        .boxed();
+        Ok(RemoteFileStream { file, chunks })
     }
 
-    async fn list_objects(&self, bucket: &str, prefix: &str) -> Result<Vec<String>> {
+    async fn list_objects(&self, bucket: &str, prefix: &str) -> Result<Vec<(String, RemoteFile)>> {
         let mut response = self
-            .client
+            .client_for_bucket(bucket)
+            .await
             .list_objects_v2()
             .bucket(bucket)
             .prefix(prefix)
             .into_paginator()
             .send();
-        let mut result_files: Vec<String> = vec![];
+        let mut result_files: Vec<(String, RemoteFile)> = vec![];
         while let Some(result) = response.next().await {
             match result {
                 Ok(objects) => {
                     let mut files = objects
                         .contents()
                         .iter()
-                        .map(|val| val.key().unwrap_or("Unknown").to_owned())
+                        .map(|val| {
+                            let file = RemoteFile {
+                                size: val
+                                    .size()
+                                    .and_then(|size| u64::try_from(size).ok())
+                                    .unwrap_or_default(),
+                                last_modified: val.last_modified().and_then(|last_modified| {
+                                    SystemTime::try_from(*last_modified).ok()
+                                }),
+                                etag: val.e_tag().map(str::to_string),
+                            };
+                            (val.key().unwrap_or("Unknown").to_owned(), file)
+                        })
                         .collect::<Vec<_>>();
                     result_files.append(&mut files);
                 }
@@ -161,7 +727,8 @@ impl ProviderClient for AwsClient {
     ) -> Result<(Vec<String>, Vec<String>)> {
         let prefix = current_folder.unwrap_or("");
         let mut response = self
-            .client
+            .client_for_bucket(bucket)
+            .await
             .list_objects_v2()
             .bucket(bucket)
             .prefix(prefix)
@@ -195,3 +762,19 @@ impl ProviderClient for AwsClient {
         Ok((result_files, result_folders))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::location_constraint_to_region;
+
+    #[test]
+    fn test_location_constraint_to_region() {
+        assert_eq!(location_constraint_to_region(None), "us-east-1");
+        assert_eq!(location_constraint_to_region(Some("")), "us-east-1");
+        assert_eq!(location_constraint_to_region(Some("EU")), "eu-west-1");
+        assert_eq!(
+            location_constraint_to_region(Some("eu-central-1")),
+            "eu-central-1"
+        );
+    }
+}
@@ -4,34 +4,43 @@ use anyhow::{anyhow, Result};
 use configparser::ini::{Ini, WriteOptions};
 use dirs::home_dir;
 
-use crate::logger::LOGGER;
-
 #[derive(Debug, Clone)]
 pub struct Credentials {
     file: String,
+    #[allow(dead_code)]
     accounts: Vec<String>,
 }
 
 pub type AuthProperties = HashMap<String, Option<String>>;
 
+pub const REDACTED_VALUE: &str = "********";
+const SECRET_PROPERTY_MARKERS: [&str; 3] = ["secret", "token", "password"];
+
+pub fn is_secret_property(property: &str) -> bool {
+    let property = property.to_lowercase();
+    SECRET_PROPERTY_MARKERS
+        .iter()
+        .any(|marker| property.contains(marker))
+}
+
+// returns a copy of the properties that is safe to log, secret values are masked
+pub fn redact_properties(properties: &AuthProperties) -> AuthProperties {
+    properties
+        .iter()
+        .map(|(key, value)| {
+            let value = if is_secret_property(key) {
+                value.as_ref().map(|_| REDACTED_VALUE.to_string())
+            } else {
+                value.clone()
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
+
 impl Default for Credentials {
     fn default() -> Self {
-        let home = home_dir();
-        let credentials_path = if let Some(home) = home {
-            let mut home_string = home.into_os_string();
-            home_string.push("/.aws/credentials");
-            home_string
-        } else {
-            panic!("Failed to find the home dir");
-        };
-        let credentials_path = match credentials_path.to_str() {
-            Some(credentials_path) => credentials_path,
-            None => panic!("Failed to get credentials file"),
-        };
-        Self {
-            file: credentials_path.to_string(),
-            accounts: vec![],
-        }
+        Self::new(None, None)
     }
 }
 
@@ -114,7 +123,7 @@ mod tests {
     use anyhow::{anyhow, Result};
     use dirs::home_dir;
 
-    use super::Credentials;
+    use super::{is_secret_property, redact_properties, Credentials, REDACTED_VALUE};
 
     #[test]
     fn create_credentials() {
@@ -245,6 +254,7 @@ aws_session_token=test_session
         Ok(())
     }
     #[test]
+    #[allow(clippy::unnecessary_get_then_check)]
     fn test_get_properties() -> Result<()> {
         let test_resources_folder = test_resources_folder!();
         fs::create_dir_all(test_resources_folder)?;
@@ -292,4 +302,37 @@ aws_session_token=test_session
 
         Ok(())
     }
+
+    #[test]
+    fn test_redact_properties() {
+        assert!(is_secret_property("aws_secret_access_key"));
+        assert!(is_secret_property("aws_session_token"));
+        assert!(is_secret_property("AWS_SECURITY_TOKEN"));
+        assert!(!is_secret_property("aws_access_key_id"));
+        assert!(!is_secret_property("region"));
+
+        let mut properties = HashMap::new();
+        properties.insert(
+            "aws_access_key_id".to_string(),
+            Some("test_key".to_string()),
+        );
+        properties.insert(
+            "aws_secret_access_key".to_string(),
+            Some("test_secret".to_string()),
+        );
+        properties.insert("aws_session_token".to_string(), None);
+
+        let redacted = redact_properties(&properties);
+
+        assert_eq!(
+            redacted.get("aws_access_key_id").unwrap().as_deref(),
+            Some("test_key")
+        );
+        assert_eq!(
+            redacted.get("aws_secret_access_key").unwrap().as_deref(),
+            Some(REDACTED_VALUE)
+        );
+        assert_eq!(redacted.get("aws_session_token").unwrap().as_deref(), None);
+        assert!(!format!("{:?}", redacted).contains("test_secret"));
+    }
 }
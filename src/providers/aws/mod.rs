@@ -1,4 +1,16 @@
+mod checksum;
 mod client;
 mod credentials;
+mod multipart;
+mod ranged;
+mod regions;
+mod retry;
+mod shared_client;
+pub use checksum::matches_etag;
 pub use client::{AccountMap, AwsClient};
-pub use credentials::{AuthProperties, Credentials};
+pub use credentials::{
+    is_secret_property, redact_properties, AuthProperties, Credentials, REDACTED_VALUE,
+};
+pub use ranged::DownloadOptions;
+pub use regions::{filter_regions, is_known_region, validate_region};
+pub use shared_client::SharedClient;
@@ -0,0 +1,247 @@
+use std::{future::Future, io::SeekFrom};
+
+use anyhow::{anyhow, Result};
+use aws_sdk_s3::{
+    primitives::ByteStream,
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client,
+};
+use bytes::Bytes;
+use futures::{stream, StreamExt};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+
+use super::{ranged::part_range, retry::with_retries};
+
+const MIB: u64 = 1024 * 1024;
+// s3 refuses smaller parts, the last one excepted
+const MIN_PART_SIZE: u64 = 5 * MIB;
+const MAX_PARTS: u64 = 10_000;
+// bigger objects can only be copied in parts
+pub const MAX_SINGLE_COPY: u64 = 5 * 1024 * MIB;
+
+// the configured part size, grown when the object would need too many parts
+pub fn part_size(total_bytes: u64, part_size: u64) -> u64 {
+    part_size
+        .max(MIN_PART_SIZE)
+        .max(total_bytes.div_ceil(MAX_PARTS))
+}
+
+// an upload that did not complete is aborted so that s3 doesn't keep its parts,
+// the request is sent in the background when the transfer is dropped or fails
+struct PendingUpload {
+    client: Client,
+    bucket: String,
+    key: String,
+    upload_id: String,
+    completed: bool,
+}
+
+impl Drop for PendingUpload {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+        let abort = self
+            .client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(&self.upload_id);
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let _ = abort.send().await;
+            });
+        }
+    }
+}
+
+// an object written part by part, several parts are sent at the same time
+pub struct MultipartUpload<'a> {
+    pub client: &'a Client,
+    pub bucket: &'a str,
+    pub key: &'a str,
+    pub total_bytes: u64,
+    pub part_size: u64,
+    pub max_attempts: u32,
+}
+
+impl MultipartUpload<'_> {
+    // send_part gets the upload id, the part number and the first and last byte of the part
+    async fn run<F, Fut>(
+        &self,
+        max_parallel_parts: usize,
+        on_progress: &mut (impl FnMut(u64, Option<u64>) + Send),
+        send_part: F,
+    ) -> Result<()>
+    where
+        F: Fn(String, i32, u64, u64) -> Fut,
+        Fut: Future<Output = Result<CompletedPart>>,
+    {
+        let created = with_retries(
+            self.max_attempts,
+            &format!("start the upload of {}", self.key),
+            || async {
+                Ok(self
+                    .client
+                    .create_multipart_upload()
+                    .bucket(self.bucket)
+                    .key(self.key)
+                    .send()
+                    .await?)
+            },
+        )
+        .await?;
+        let upload_id = created
+            .upload_id()
+            .ok_or_else(|| anyhow!("no upload id returned for {}", self.key))?
+            .to_string();
+        let mut pending = PendingUpload {
+            client: self.client.clone(),
+            bucket: self.bucket.to_string(),
+            key: self.key.to_string(),
+            upload_id: upload_id.clone(),
+            completed: false,
+        };
+
+        on_progress(0, Some(self.total_bytes));
+        let parts = self.total_bytes.div_ceil(self.part_size);
+        let jobs = (0..parts).map(|part| {
+            let (start, end) = part_range(part, self.part_size, self.total_bytes);
+            let sent = send_part(upload_id.clone(), part as i32 + 1, start, end);
+            async move { sent.await.map(|part| (part, end - start + 1)) }
+        });
+        let mut jobs = stream::iter(jobs).buffer_unordered(max_parallel_parts.max(1));
+        let mut completed_parts = vec![];
+        let mut uploaded = 0;
+        while let Some(job) = jobs.next().await {
+            let (part, bytes) = job?;
+            uploaded += bytes;
+            on_progress(uploaded, Some(self.total_bytes));
+            completed_parts.push(part);
+        }
+        completed_parts.sort_by_key(|part| part.part_number());
+
+        let completed_upload = CompletedMultipartUpload::builder()
+            .set_parts(Some(completed_parts))
+            .build();
+        with_retries(
+            self.max_attempts,
+            &format!("complete the upload of {}", self.key),
+            || async {
+                self.client
+                    .complete_multipart_upload()
+                    .bucket(self.bucket)
+                    .key(self.key)
+                    .upload_id(&upload_id)
+                    .multipart_upload(completed_upload.clone())
+                    .send()
+                    .await?;
+                Ok(())
+            },
+        )
+        .await?;
+        pending.completed = true;
+        Ok(())
+    }
+
+    // every part is read in memory before being sent, so that it can be sent again
+    // after a transient failure
+    pub async fn upload_file(
+        &self,
+        file_name: &str,
+        max_parallel_parts: usize,
+        on_progress: &mut (impl FnMut(u64, Option<u64>) + Send),
+    ) -> Result<()> {
+        self.run(
+            max_parallel_parts,
+            on_progress,
+            |upload_id, part_number, start, end| async move {
+                let body = read_range(file_name, start, end).await?;
+                with_retries(
+                    self.max_attempts,
+                    &format!("upload part {part_number} of {}", self.key),
+                    || async {
+                        let uploaded = self
+                            .client
+                            .upload_part()
+                            .bucket(self.bucket)
+                            .key(self.key)
+                            .upload_id(&upload_id)
+                            .part_number(part_number)
+                            .body(ByteStream::from(body.clone()))
+                            .send()
+                            .await?;
+                        Ok(CompletedPart::builder()
+                            .part_number(part_number)
+                            .set_e_tag(uploaded.e_tag().map(str::to_string))
+                            .build())
+                    },
+                )
+                .await
+            },
+        )
+        .await
+    }
+
+    // the parts are copied by s3 from byte ranges of the source object
+    pub async fn copy(&self, copy_source: &str, max_parallel_parts: usize) -> Result<()> {
+        self.run(
+            max_parallel_parts,
+            &mut |_, _| {},
+            |upload_id, part_number, start, end| async move {
+                with_retries(
+                    self.max_attempts,
+                    &format!("copy part {part_number} of {}", self.key),
+                    || async {
+                        let copied = self
+                            .client
+                            .upload_part_copy()
+                            .copy_source(copy_source)
+                            .copy_source_range(format!("bytes={start}-{end}"))
+                            .bucket(self.bucket)
+                            .key(self.key)
+                            .upload_id(&upload_id)
+                            .part_number(part_number)
+                            .send()
+                            .await?;
+                        Ok(CompletedPart::builder()
+                            .part_number(part_number)
+                            .set_e_tag(
+                                copied
+                                    .copy_part_result()
+                                    .and_then(|result| result.e_tag())
+                                    .map(str::to_string),
+                            )
+                            .build())
+                    },
+                )
+                .await
+            },
+        )
+        .await
+    }
+}
+
+// bytes of the file from start to end, both inclusive
+async fn read_range(file_name: &str, start: u64, end: u64) -> Result<Bytes> {
+    let mut file = File::open(file_name).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+    let mut buffer = vec![0; usize::try_from(end + 1 - start)?];
+    file.read_exact(&mut buffer).await?;
+    Ok(Bytes::from(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_the_part_size_within_the_limits_of_s3() {
+        assert_eq!(part_size(100 * MIB, MIB), MIN_PART_SIZE);
+        assert_eq!(part_size(100 * MIB, 16 * MIB), 16 * MIB);
+        assert_eq!(part_size(MAX_PARTS * 32 * MIB, 16 * MIB), 32 * MIB);
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::HashSet,
+    io::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use aws_sdk_s3::{primitives::ByteStream, Client};
+use futures::{future::BoxFuture, stream, FutureExt, StreamExt};
+
+use crate::providers::PartialDownload;
+
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    // objects bigger than this are downloaded in ranges and uploaded in parts
+    pub ranged_threshold: u64,
+    pub part_size: u64,
+    pub max_parallel_parts: usize,
+    // downloaded files get the last modified date of their object
+    pub preserve_modified_time: bool,
+    // attempts made before a download failing with a transient error is given up
+    pub max_attempts: u32,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            ranged_threshold: 64 * MIB,
+            part_size: 16 * MIB,
+            max_parallel_parts: 4,
+            preserve_modified_time: true,
+            max_attempts: 3,
+        }
+    }
+}
+
+// first and last byte of the part, both inclusive as in the range header
+pub fn part_range(part: u64, part_size: u64, total_bytes: u64) -> (u64, u64) {
+    let start = part * part_size;
+    (start, (start + part_size).min(total_bytes) - 1)
+}
+
+async fn write_part(
+    mut body: ByteStream,
+    partial_download: &PartialDownload,
+    offset: u64,
+    expected_bytes: u64,
+    downloaded: Arc<AtomicU64>,
+) -> Result<()> {
+    let mut part = partial_download.open_at(offset)?;
+    let mut written = 0;
+    // the body of the first part might be the whole object, only its range is kept
+    while written < expected_bytes {
+        let Some(bytes) = body.try_next().await? else {
+            break;
+        };
+        let remaining = usize::try_from(expected_bytes - written).unwrap_or(usize::MAX);
+        let bytes = &bytes[..bytes.len().min(remaining)];
+        part.write_all(bytes)?;
+        written += bytes.len() as u64;
+        downloaded.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+    }
+    if written != expected_bytes {
+        return Err(anyhow!(
+            "range starting at {offset} ended after {written} of {expected_bytes} bytes"
+        ));
+    }
+    part.flush()?;
+    Ok(())
+}
+
+pub struct RangedDownload<'a> {
+    pub client: &'a Client,
+    pub bucket: &'a str,
+    pub key: &'a str,
+    pub etag: &'a str,
+    pub total_bytes: u64,
+    pub part_size: u64,
+    pub partial_download: &'a PartialDownload,
+}
+
+impl RangedDownload<'_> {
+    async fn fetch_part(&self, start: u64, end: u64, downloaded: Arc<AtomicU64>) -> Result<()> {
+        let object = self
+            .client
+            .get_object()
+            .bucket(self.bucket)
+            .key(self.key)
+            .range(format!("bytes={start}-{end}"))
+            .if_match(self.etag)
+            .send()
+            .await?;
+        if object.e_tag() != Some(self.etag) {
+            return Err(anyhow!("object {} changed during the download", self.key));
+        }
+        write_part(
+            object.body,
+            self.partial_download,
+            start,
+            end - start + 1,
+            downloaded,
+        )
+        .await
+    }
+
+    // first_part is the body of a plain get that already started streaming the object
+    pub async fn download(
+        &self,
+        first_part: Option<ByteStream>,
+        completed: &HashSet<u64>,
+        max_parallel_parts: usize,
+        on_progress: &mut (impl FnMut(u64, Option<u64>) + Send),
+    ) -> Result<()> {
+        let parts = self.total_bytes.div_ceil(self.part_size);
+        let already_downloaded = completed
+            .iter()
+            .filter(|part| **part < parts)
+            .map(|part| {
+                let (start, end) = part_range(*part, self.part_size, self.total_bytes);
+                end - start + 1
+            })
+            .sum();
+        let downloaded = Arc::new(AtomicU64::new(already_downloaded));
+        on_progress(already_downloaded, Some(self.total_bytes));
+
+        let mut first_part = first_part;
+        let jobs = (0..parts)
+            .filter(|part| !completed.contains(part))
+            .map(|part| {
+                let (start, end) = part_range(part, self.part_size, self.total_bytes);
+                let downloaded = downloaded.clone();
+                let job: BoxFuture<'_, Result<u64>> = match first_part.take().filter(|_| part == 0)
+                {
+                    Some(body) => async move {
+                        write_part(
+                            body,
+                            self.partial_download,
+                            start,
+                            end - start + 1,
+                            downloaded,
+                        )
+                        .await
+                        .map(|_| part)
+                    }
+                    .boxed(),
+                    None => {
+                        async move { self.fetch_part(start, end, downloaded).await.map(|_| part) }
+                            .boxed()
+                    }
+                };
+                job
+            })
+            .collect::<Vec<_>>();
+
+        let mut jobs = stream::iter(jobs).buffer_unordered(max_parallel_parts.max(1));
+        let mut ticker = tokio::time::interval(Duration::from_millis(100));
+        loop {
+            tokio::select! {
+                job = jobs.next() => match job {
+                    Some(part) => {
+                        self.partial_download.mark_part_done(part?)?;
+                        on_progress(downloaded.load(Ordering::Relaxed), Some(self.total_bytes));
+                    }
+                    None => break,
+                },
+                _ = ticker.tick() => {
+                    on_progress(downloaded.load(Ordering::Relaxed), Some(self.total_bytes));
+                }
+            }
+        }
+
+        let part_len = self.partial_download.part_len()?;
+        let downloaded = downloaded.load(Ordering::Relaxed);
+        if part_len != self.total_bytes || downloaded != self.total_bytes {
+            return Err(anyhow!(
+                "downloaded {downloaded} bytes of {} for {}",
+                self.total_bytes,
+                self.key
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_object_in_ranges() {
+        assert_eq!(part_range(0, 4, 10), (0, 3));
+        assert_eq!(part_range(1, 4, 10), (4, 7));
+        assert_eq!(part_range(2, 4, 10), (8, 9));
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{anyhow, Result};
+
+pub const KNOWN_REGIONS: [&str; 36] = [
+    "us-east-1",
+    "us-east-2",
+    "us-west-1",
+    "us-west-2",
+    "af-south-1",
+    "ap-east-1",
+    "ap-south-1",
+    "ap-south-2",
+    "ap-southeast-1",
+    "ap-southeast-2",
+    "ap-southeast-3",
+    "ap-southeast-4",
+    "ap-southeast-5",
+    "ap-southeast-7",
+    "ap-northeast-1",
+    "ap-northeast-2",
+    "ap-northeast-3",
+    "ca-central-1",
+    "ca-west-1",
+    "eu-central-1",
+    "eu-central-2",
+    "eu-west-1",
+    "eu-west-2",
+    "eu-west-3",
+    "eu-south-1",
+    "eu-south-2",
+    "eu-north-1",
+    "il-central-1",
+    "me-south-1",
+    "me-central-1",
+    "mx-central-1",
+    "sa-east-1",
+    "us-gov-east-1",
+    "us-gov-west-1",
+    "cn-north-1",
+    "cn-northwest-1",
+];
+
+pub fn is_known_region(region: &str) -> bool {
+    KNOWN_REGIONS.contains(&region)
+}
+
+// custom regions are allowed (e.g. s3 compatible endpoints) as long as they
+// look like a region name
+pub fn validate_region(region: &str) -> Result<()> {
+    if region.is_empty() {
+        return Err(anyhow!("region cannot be empty"));
+    }
+    if !region
+        .chars()
+        .all(|val| val.is_ascii_lowercase() || val.is_ascii_digit() || val == '-')
+    {
+        return Err(anyhow!(
+            "region {region} can only contain lowercase letters, digits and dashes"
+        ));
+    }
+    Ok(())
+}
+
+pub fn filter_regions(filter: &str) -> Vec<&'static str> {
+    let filter = filter.to_lowercase();
+    KNOWN_REGIONS
+        .iter()
+        .filter(|region| region.contains(filter.as_str()))
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{filter_regions, is_known_region, validate_region};
+
+    #[test]
+    fn test_validate_region() {
+        assert!(is_known_region("eu-central-1"));
+        assert!(!is_known_region("eu-centrl-1"));
+
+        assert!(validate_region("eu-central-1").is_ok());
+        assert!(validate_region("minio-local").is_ok());
+        assert!(validate_region("").is_err());
+        assert!(validate_region("eu central 1").is_err());
+        assert!(validate_region("EU-CENTRAL-1").is_err());
+    }
+
+    #[test]
+    fn test_filter_regions() {
+        assert_eq!(
+            filter_regions("EU-WEST"),
+            vec!["eu-west-1", "eu-west-2", "eu-west-3"]
+        );
+        assert!(filter_regions("northeast-2").contains(&"ap-northeast-2"));
+        assert!(filter_regions("mars").is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{future::Future, time::Duration};
+
+use anyhow::{Error, Result};
+use aws_sdk_s3::{
+    error::SdkError,
+    operation::{
+        complete_multipart_upload::CompleteMultipartUploadError, copy_object::CopyObjectError,
+        create_multipart_upload::CreateMultipartUploadError, get_object::GetObjectError,
+        head_object::HeadObjectError, put_object::PutObjectError, upload_part::UploadPartError,
+        upload_part_copy::UploadPartCopyError,
+    },
+    primitives::ByteStreamError,
+};
+
+use crate::logger::LOGGER;
+
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+// delay before the given retry, doubled every time
+pub fn backoff(retry: u32) -> Duration {
+    BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+        .min(MAX_DELAY)
+}
+
+// runs the operation again after a transient failure, up to max_attempts times,
+// the label tells what failed in the log
+pub async fn with_retries<T, F, Fut>(max_attempts: u32, label: &str, mut operation: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Err(e) if attempt < max_attempts && is_transient(&e) => {
+                let _ = LOGGER.info(&format!(
+                    "attempt {attempt} to {label} failed, retrying {e:?}"
+                ));
+                tokio::time::sleep(backoff(attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// network failures, timeouts, throttling and server errors are worth another
+// attempt, anything else would fail the same way again
+pub fn is_transient(error: &Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(error) = cause.downcast_ref::<SdkError<GetObjectError>>() {
+            return is_transient_sdk_error(error);
+        }
+        if let Some(error) = cause.downcast_ref::<SdkError<HeadObjectError>>() {
+            return is_transient_sdk_error(error);
+        }
+        if let Some(error) = cause.downcast_ref::<SdkError<PutObjectError>>() {
+            return is_transient_sdk_error(error);
+        }
+        if let Some(error) = cause.downcast_ref::<SdkError<CopyObjectError>>() {
+            return is_transient_sdk_error(error);
+        }
+        if let Some(error) = cause.downcast_ref::<SdkError<CreateMultipartUploadError>>() {
+            return is_transient_sdk_error(error);
+        }
+        if let Some(error) = cause.downcast_ref::<SdkError<UploadPartError>>() {
+            return is_transient_sdk_error(error);
+        }
+        if let Some(error) = cause.downcast_ref::<SdkError<UploadPartCopyError>>() {
+            return is_transient_sdk_error(error);
+        }
+        if let Some(error) = cause.downcast_ref::<SdkError<CompleteMultipartUploadError>>() {
+            return is_transient_sdk_error(error);
+        }
+        cause.is::<ByteStreamError>()
+    })
+}
+
+fn is_transient_sdk_error<E>(error: &SdkError<E>) -> bool {
+    match error {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+            true
+        }
+        _ => error.raw_response().is_some_and(|response| {
+            let status = response.status().as_u16();
+            status == 429 || status >= 500
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doubles_the_delay_up_to_a_limit() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+        assert_eq!(backoff(10), MAX_DELAY);
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+use anyhow::Result;
+
+use crate::providers::traits::ProviderClient;
+
+use super::{AccountMap, AuthProperties, AwsClient};
+
+// the client is swapped as a whole when the account or the region changes,
+// readers take a snapshot of the current one and never wait on each other.
+// operations already running keep using the snapshot they started with
+#[derive(Debug, Clone)]
+pub struct SharedClient {
+    current: Arc<RwLock<Arc<AwsClient>>>,
+}
+
+impl SharedClient {
+    pub fn new(client: AwsClient) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(client))),
+        }
+    }
+
+    pub fn snapshot(&self) -> Arc<AwsClient> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn publish(&self, client: AwsClient) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(client);
+    }
+
+    // the client might have been replaced while the snapshot was being updated,
+    // in that case the update belongs to a stale client and is dropped
+    fn publish_if_current(&self, snapshot: &Arc<AwsClient>, client: AwsClient) {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        if Arc::ptr_eq(&current, snapshot) {
+            *current = Arc::new(client);
+        }
+    }
+
+    pub async fn switch_account(&self, new_account: &str) {
+        let mut client = (*self.snapshot()).clone();
+        client.switch_account(new_account).await;
+        self.publish(client);
+    }
+
+    pub async fn change_region(&self, region: String) {
+        let mut client = (*self.snapshot()).clone();
+        client.change_region(region).await;
+        self.publish(client);
+    }
+
+    pub fn update_account(&self, account: &str, properties: AuthProperties) -> Result<AccountMap> {
+        let snapshot = self.snapshot();
+        let mut client = (*snapshot).clone();
+        let account_map = client.update_account(account, properties)?;
+        self.publish_if_current(&snapshot, client);
+        Ok(account_map)
+    }
+}
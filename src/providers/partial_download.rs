@@ -0,0 +1,234 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+
+// a download is written to `<destination>.part` and moved in place once complete,
+// the etag of the object is kept next to it so an interrupted download can be
+// resumed only if the object did not change in the meantime.
+// downloads split in ranges also record the object size, the part size and the
+// index of every completed part, one per line
+#[derive(Debug)]
+pub struct PartialDownload {
+    destination: PathBuf,
+    part: PathBuf,
+    etag_file: PathBuf,
+}
+
+impl PartialDownload {
+    pub fn new(destination: &str) -> Self {
+        Self {
+            destination: PathBuf::from(destination),
+            part: PathBuf::from(format!("{destination}.part")),
+            etag_file: PathBuf::from(format!("{destination}.part.etag")),
+        }
+    }
+
+    fn read_etag_file(&self) -> Option<Vec<String>> {
+        let content = fs::read_to_string(&self.etag_file).ok()?;
+        Some(content.lines().map(|line| line.to_string()).collect())
+    }
+
+    // etag and amount of bytes already downloaded
+    pub fn resume_point(&self) -> Option<(String, u64)> {
+        let [etag] = &self.read_etag_file()?[..] else {
+            return None;
+        };
+        let downloaded = fs::metadata(&self.part).ok()?.len();
+        if etag.is_empty() || downloaded == 0 {
+            return None;
+        }
+        Some((etag.to_string(), downloaded))
+    }
+
+    pub fn ranged_resume_point(&self) -> Option<RangedResumePoint> {
+        let lines = self.read_etag_file()?;
+        let [etag, ranges, completed @ ..] = &lines[..] else {
+            return None;
+        };
+        let [total_bytes, part_size] = ranges
+            .strip_prefix("ranges ")?
+            .split(' ')
+            .map(|value| value.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?[..]
+        else {
+            return None;
+        };
+        if fs::metadata(&self.part).ok()?.len() != total_bytes {
+            return None;
+        }
+        Some(RangedResumePoint {
+            etag: etag.to_string(),
+            total_bytes,
+            part_size,
+            // a line might have been cut short while being written
+            completed: completed
+                .iter()
+                .filter_map(|part| part.parse().ok())
+                .collect(),
+        })
+    }
+
+    pub fn start(&self, etag: Option<&str>) -> Result<File> {
+        match etag {
+            Some(etag) => fs::write(&self.etag_file, etag)?,
+            None => {
+                let _ = fs::remove_file(&self.etag_file);
+            }
+        }
+        Ok(OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.part)?)
+    }
+
+    // the part file is allocated upfront, every range is written at its own offset
+    pub fn start_ranged(&self, etag: &str, total_bytes: u64, part_size: u64) -> Result<()> {
+        fs::write(
+            &self.etag_file,
+            format!("{etag}\nranges {total_bytes} {part_size}\n"),
+        )?;
+        let part = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&self.part)?;
+        part.set_len(total_bytes)?;
+        Ok(())
+    }
+
+    pub fn open_at(&self, offset: u64) -> Result<File> {
+        let mut part = OpenOptions::new().write(true).open(&self.part)?;
+        part.seek(SeekFrom::Start(offset))?;
+        Ok(part)
+    }
+
+    pub fn mark_part_done(&self, part: u64) -> Result<()> {
+        let mut etag_file = OpenOptions::new().append(true).open(&self.etag_file)?;
+        writeln!(etag_file, "{part}")?;
+        Ok(())
+    }
+
+    pub fn part_path(&self) -> &Path {
+        &self.part
+    }
+
+    // the date survives the rename to the destination
+    pub fn set_modified(&self, modified: SystemTime) -> Result<()> {
+        OpenOptions::new()
+            .write(true)
+            .open(&self.part)?
+            .set_modified(modified)?;
+        Ok(())
+    }
+
+    pub fn part_len(&self) -> Result<u64> {
+        Ok(fs::metadata(&self.part)?.len())
+    }
+
+    pub fn resume(&self) -> Result<File> {
+        Ok(OpenOptions::new().append(true).open(&self.part)?)
+    }
+
+    // the destination is only ever replaced by a complete file, synced before
+    // the rename so a crash can't expose a truncated file under the real name
+    pub fn finish(&self) -> Result<()> {
+        File::open(&self.part)?.sync_all()?;
+        fs::rename(&self.part, &self.destination)?;
+        let _ = fs::remove_file(&self.etag_file);
+        Ok(())
+    }
+
+    pub fn discard(&self) {
+        let _ = fs::remove_file(&self.part);
+        let _ = fs::remove_file(&self.etag_file);
+    }
+
+    // a failed download is kept only when it can be resumed later
+    pub fn discard_if_not_resumable(&self) {
+        if self.resume_point().is_none() && self.ranged_resume_point().is_none() {
+            self.discard();
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RangedResumePoint {
+    pub etag: String,
+    pub total_bytes: u64,
+    pub part_size: u64,
+    pub completed: HashSet<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumes_only_with_a_recorded_etag() {
+        let dir = std::env::temp_dir().join(format!("s3li-partial-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let destination = dir.join("file.txt");
+        let partial = PartialDownload::new(destination.to_str().unwrap());
+
+        fs::write(&destination, "good copy").unwrap();
+        let mut part = partial.start(None).unwrap();
+        part.write_all(b"hello").unwrap();
+        assert_eq!(partial.resume_point(), None);
+        partial.discard_if_not_resumable();
+        assert!(!partial.part_path().exists());
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "good copy");
+
+        let mut part = partial.start(Some("\"etag\"")).unwrap();
+        part.write_all(b"hello").unwrap();
+        assert_eq!(partial.resume_point(), Some(("\"etag\"".to_string(), 5)));
+
+        partial.discard_if_not_resumable();
+        partial.resume().unwrap().write_all(b" world").unwrap();
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "good copy");
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        partial.set_modified(modified).unwrap();
+        partial.finish().unwrap();
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "hello world");
+        assert_eq!(
+            fs::metadata(&destination).unwrap().modified().unwrap(),
+            modified
+        );
+        assert_eq!(partial.resume_point(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn records_completed_ranges() {
+        let dir = std::env::temp_dir().join(format!("s3li-ranged-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let destination = dir.join("file.txt");
+        let partial = PartialDownload::new(destination.to_str().unwrap());
+
+        partial.start_ranged("\"etag\"", 10, 4).unwrap();
+        partial.open_at(4).unwrap().write_all(b"4567").unwrap();
+        partial.mark_part_done(1).unwrap();
+        assert_eq!(partial.resume_point(), None);
+        assert_eq!(
+            partial.ranged_resume_point(),
+            Some(RangedResumePoint {
+                etag: "\"etag\"".to_string(),
+                total_bytes: 10,
+                part_size: 4,
+                completed: HashSet::from([1]),
+            })
+        );
+        assert_eq!(partial.part_len().unwrap(), 10);
+
+        partial.discard();
+        assert_eq!(partial.ranged_resume_point(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
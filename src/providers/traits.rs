@@ -1,12 +1,31 @@
+use std::{collections::HashMap, time::SystemTime};
+
 use anyhow::Result;
+use bytes::Bytes;
+use futures::stream::BoxStream;
 
 use super::{AccountMap, AuthProperties};
 
+#[derive(Debug, Clone)]
+pub struct RemoteFile {
+    pub size: u64,
+    pub last_modified: Option<SystemTime>,
+    pub etag: Option<String>,
+}
+
+// an object read as it arrives, for downloads that don't end up in a file of their own
+pub struct RemoteFileStream {
+    pub file: RemoteFile,
+    pub chunks: BoxStream<'static, Result<Bytes>>,
+}
+
 pub trait ProviderClient: Send {
     async fn switch_account(&mut self, new_account: &str);
     async fn change_region(&mut self, region: String);
 
     async fn list_buckets(&self) -> Result<Vec<String>>;
+    // regions of the buckets used so far
+    fn bucket_regions(&self) -> HashMap<String, String>;
 
     fn list_accounts(&self) -> Result<AccountMap>;
 
@@ -17,9 +36,44 @@ pub trait ProviderClient: Send {
         bucket: &str,
         file_key: &str,
         file_name: &str,
+        on_progress: impl FnMut(u64, Option<u64>) + Send,
     ) -> impl std::future::Future<Output = Result<bool>> + std::marker::Send;
 
-    async fn list_objects(&self, bucket: &str, prefix: &str) -> Result<Vec<String>>;
+    fn file_metadata(
+        &self,
+        bucket: &str,
+        file_key: &str,
+    ) -> impl std::future::Future<Output = Result<RemoteFile>> + std::marker::Send;
+
+    fn upload_file(
+        &self,
+        bucket: &str,
+        file_key: &str,
+        file_name: &str,
+        on_progress: impl FnMut(u64, Option<u64>) + Send,
+    ) -> impl std::future::Future<Output = Result<bool>> + std::marker::Send;
+
+    fn copy_object(
+        &self,
+        source_bucket: &str,
+        source_key: &str,
+        bucket: &str,
+        file_key: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + std::marker::Send;
+
+    fn delete_object(
+        &self,
+        bucket: &str,
+        file_key: &str,
+    ) -> impl std::future::Future<Output = Result<()>> + std::marker::Send;
+
+    fn open_file(
+        &self,
+        bucket: &str,
+        file_key: &str,
+    ) -> impl std::future::Future<Output = Result<RemoteFileStream>> + std::marker::Send;
+
+    async fn list_objects(&self, bucket: &str, prefix: &str) -> Result<Vec<(String, RemoteFile)>>;
     async fn list_objects_in_folder(
         &self,
         bucket: &str,
@@ -1,6 +1,15 @@
 mod accounts;
 mod action_manager;
+pub mod archives;
+pub mod conflicts;
+pub mod destinations;
+pub mod diff;
 pub mod explorer;
+pub mod filters;
 pub mod notifications;
+pub mod preferences;
 pub mod sources;
 pub mod state;
+pub mod sync;
+pub mod tasks;
+pub mod transfers;
@@ -1,9 +1,6 @@
-use std::sync::Arc;
-
 use anyhow::Result;
-use tokio::sync::Mutex;
 
-use crate::providers::{AccountMap, AuthProperties, AwsClient, ProviderClient};
+use crate::providers::{validate_region, AccountMap, AuthProperties, ProviderClient, SharedClient};
 
 #[derive(Debug, Clone)]
 pub struct Accounts {
@@ -11,15 +8,12 @@ pub struct Accounts {
     pub available_accounts: Vec<String>,
     pub active_account: Option<String>,
     pub region: String,
-    client: Arc<Mutex<AwsClient>>,
+    client: SharedClient,
 }
 
 impl Accounts {
-    pub async fn new(
-        client: Arc<Mutex<AwsClient>>,
-        active_account: Option<String>,
-    ) -> Result<Self> {
-        let account_map = client.clone().lock().await.list_accounts()?;
+    pub async fn new(client: SharedClient, active_account: Option<String>) -> Result<Self> {
+        let account_map = client.snapshot().list_accounts()?;
         let available_accounts: Vec<String> =
             Accounts::extract_available_account_from_account_map(&account_map);
         Ok(Self {
@@ -27,7 +21,7 @@ impl Accounts {
             account_map,
             available_accounts,
             active_account,
-            region: client.clone().lock().await.region.clone(),
+            region: client.snapshot().region.clone(),
         })
     }
 
@@ -38,17 +32,19 @@ impl Accounts {
             .map(|val| val.as_str())
             .unwrap_or("default");
         self.active_account = Some(account.to_string());
-        self.client.lock().await.switch_account(account).await;
+        self.client.switch_account(account).await;
         &account
     }
 
-    pub async fn change_region(&mut self, new_region: String) {
+    pub async fn change_region(&mut self, new_region: String) -> Result<()> {
+        validate_region(&new_region)?;
         self.region = new_region.clone();
-        self.client.lock().await.change_region(new_region).await;
+        self.client.change_region(new_region).await;
+        Ok(())
     }
 
     pub async fn refresh_credentials(&mut self) -> Result<()> {
-        let account_map = self.client.lock().await.list_accounts()?;
+        let account_map = self.client.snapshot().list_accounts()?;
 
         let mut available_accounts: Vec<String> = account_map
             .clone()
@@ -68,11 +64,7 @@ impl Accounts {
         account: String,
         properties: AuthProperties,
     ) -> Result<()> {
-        let account_map = self
-            .client
-            .lock()
-            .await
-            .update_account(&account, properties)?;
+        let account_map = self.client.update_account(&account, properties)?;
         self.account_map = account_map;
         self.available_accounts =
             Accounts::extract_available_account_from_account_map(&self.account_map);
@@ -2,6 +2,7 @@ use time::OffsetDateTime;
 
 use crate::{action::Action, logger::LOGGER};
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct Operation {
     timestamp: String,
@@ -16,12 +17,13 @@ pub struct ActionManager {
 impl ActionManager {
     pub fn push(&mut self, action: Action) {
         let now = OffsetDateTime::now_utc();
+        // never keep secrets in the history, it ends up in the log file
         let operation = Operation {
             timestamp: format!("{}", now),
-            action,
+            action: action.redacted(),
         };
 
-        LOGGER.info(&format!("Running Action {:#?}", operation));
+        let _ = LOGGER.info(&format!("Running Action {:#?}", operation));
         self.actions.push(operation);
     }
 }
@@ -0,0 +1,319 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Result};
+use bytes::{Buf, Bytes};
+use flate2::{write::GzEncoder, Compression};
+use futures::StreamExt;
+use time::OffsetDateTime;
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender},
+    task::JoinHandle,
+};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::providers::{PartialDownload, RemoteFileStream};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    // order of the formats in the download popup, None downloads plain files
+    pub fn next(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(ArchiveFormat::TarGz),
+            Some(ArchiveFormat::TarGz) => Some(ArchiveFormat::Zip),
+            Some(ArchiveFormat::Zip) => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => ".tar.gz",
+            ArchiveFormat::Zip => ".zip",
+        }
+    }
+
+    pub fn with_extension(&self, name: &str) -> String {
+        if name.ends_with(self.extension()) {
+            name.to_string()
+        } else {
+            format!("{name}{}", self.extension())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveSettings {
+    pub format: ArchiveFormat,
+    // file name of the archive inside the destination, extension included
+    pub name: String,
+}
+
+struct ArchiveEntry {
+    path: String,
+    size: u64,
+    last_modified: Option<SystemTime>,
+    chunks: Receiver<Result<Bytes>>,
+}
+
+// chunks of the entry being written, handed over from the async side
+struct ChunkReader {
+    chunks: Receiver<Result<Bytes>>,
+    current: Bytes,
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.current.is_empty() {
+            match self.chunks.blocking_recv() {
+                Some(Ok(chunk)) => self.current = chunk,
+                Some(Err(e)) => return Err(io::Error::other(format!("{e:#}"))),
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.current.len());
+        buf[..len].copy_from_slice(&self.current[..len]);
+        self.current.advance(len);
+        Ok(len)
+    }
+}
+
+// the archive is compressed on a blocking thread while the files are streamed
+// into it one after the other, nothing but the archive touches the disk.
+// like downloads it is written next to its destination and moved in place once complete
+pub struct ArchiveWriter {
+    partial_download: PartialDownload,
+    entries: Sender<ArchiveEntry>,
+    writer: JoinHandle<Result<()>>,
+}
+
+impl ArchiveWriter {
+    pub fn create(path: &str, format: ArchiveFormat) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial_download = PartialDownload::new(path);
+        let file = partial_download.start(None)?;
+        let (entries, receiver) = mpsc::channel(1);
+        let writer = tokio::task::spawn_blocking(move || match format {
+            ArchiveFormat::TarGz => write_tar_gz(file, receiver),
+            ArchiveFormat::Zip => write_zip(file, receiver),
+        });
+        Ok(Self {
+            partial_download,
+            entries,
+            writer,
+        })
+    }
+
+    // streams a file into the archive under its relative path, on_progress receives
+    // the bytes of the file written so far
+    pub async fn add(
+        &self,
+        path: String,
+        remote_file: RemoteFileStream,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<()> {
+        let (chunks, receiver) = mpsc::channel(16);
+        let closed = || anyhow!("The archive stopped being written");
+        self.entries
+            .send(ArchiveEntry {
+                path,
+                size: remote_file.file.size,
+                last_modified: remote_file.file.last_modified,
+                chunks: receiver,
+            })
+            .await
+            .map_err(|_| closed())?;
+        let mut stream = remote_file.chunks;
+        let mut written = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    // the entry can't be completed, the archive is failed with it
+                    let _ = chunks.send(Err(anyhow!("{e:#}"))).await;
+                    return Err(e);
+                }
+            };
+            written += chunk.len() as u64;
+            chunks.send(Ok(chunk)).await.map_err(|_| closed())?;
+            on_progress(written);
+        }
+        if written != remote_file.file.size {
+            let error = anyhow!("Received {written} bytes out of {}", remote_file.file.size);
+            let _ = chunks.send(Err(anyhow!("{error:#}"))).await;
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    pub async fn finish(self) -> Result<()> {
+        drop(self.entries);
+        let written = self.writer.await?;
+        match written {
+            Ok(_) => self.partial_download.finish(),
+            Err(e) => {
+                self.partial_download.discard();
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn discard(self) {
+        drop(self.entries);
+        let _ = self.writer.await;
+        self.partial_download.discard();
+    }
+}
+
+fn write_tar_gz(file: File, mut entries: Receiver<ArchiveEntry>) -> Result<()> {
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    while let Some(entry) = entries.blocking_recv() {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(entry.size);
+        header.set_mode(0o644);
+        if let Some(last_modified) = entry.last_modified {
+            header.set_mtime(OffsetDateTime::from(last_modified).unix_timestamp().max(0) as u64);
+        }
+        builder.append_data(
+            &mut header,
+            &entry.path,
+            ChunkReader {
+                chunks: entry.chunks,
+                current: Bytes::new(),
+            },
+        )?;
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn write_zip(file: File, mut entries: Receiver<ArchiveEntry>) -> Result<()> {
+    let mut zip = ZipWriter::new(file);
+    while let Some(entry) = entries.blocking_recv() {
+        let mut options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(entry.size >= u32::MAX as u64)
+            .unix_permissions(0o644);
+        if let Some(last_modified) = entry.last_modified.and_then(to_zip_time) {
+            options = options.last_modified_time(last_modified);
+        }
+        zip.start_file(entry.path, options)?;
+        io::copy(
+            &mut ChunkReader {
+                chunks: entry.chunks,
+                current: Bytes::new(),
+            },
+            &mut zip,
+        )?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+// zip dates have no time zone and only cover 1980 to 2107
+fn to_zip_time(time: SystemTime) -> Option<zip::DateTime> {
+    let time = OffsetDateTime::from(time);
+    zip::DateTime::from_date_and_time(
+        u16::try_from(time.year()).ok()?,
+        time.month() as u8,
+        time.day(),
+        time.hour(),
+        time.minute(),
+        time.second(),
+    )
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Read};
+
+    use flate2::read::GzDecoder;
+    use futures::stream;
+
+    use super::*;
+    use crate::providers::RemoteFile;
+
+    fn remote_file(content: &'static str) -> RemoteFileStream {
+        RemoteFileStream {
+            file: RemoteFile {
+                size: content.len() as u64,
+                last_modified: None,
+                etag: None,
+            },
+            chunks: stream::iter(
+                content
+                    .as_bytes()
+                    .chunks(3)
+                    .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+                    .collect::<Vec<_>>(),
+            )
+            .boxed(),
+        }
+    }
+
+    async fn write_archive(path: &str, format: ArchiveFormat) {
+        let writer = ArchiveWriter::create(path, format).unwrap();
+        writer
+            .add("a.txt".to_string(), remote_file("first file"), |_| {})
+            .await
+            .unwrap();
+        writer
+            .add("b/c.txt".to_string(), remote_file("nested file"), |_| {})
+            .await
+            .unwrap();
+        writer.finish().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_the_relative_paths_of_the_entries() {
+        let dir = std::env::temp_dir().join(format!("s3li-archives-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let tar_path = dir.join("download.tar.gz").to_string_lossy().to_string();
+        write_archive(&tar_path, ArchiveFormat::TarGz).await;
+        let mut tar = tar::Archive::new(GzDecoder::new(fs::File::open(&tar_path).unwrap()));
+        let entries = tar
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (entry.path().unwrap().to_string_lossy().to_string(), content)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                ("a.txt".to_string(), "first file".to_string()),
+                ("b/c.txt".to_string(), "nested file".to_string())
+            ]
+        );
+
+        let zip_path = dir.join("download.zip").to_string_lossy().to_string();
+        write_archive(&zip_path, ArchiveFormat::Zip).await;
+        let mut zip = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+        let mut content = String::new();
+        zip.by_name("b/c.txt")
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "nested file");
+        assert_eq!(zip.len(), 2);
+        assert!(!Path::new(&format!("{zip_path}.part")).exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
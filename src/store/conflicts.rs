@@ -0,0 +1,164 @@
+use std::{
+    fs::{self, Metadata},
+    future::Future,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+
+use crate::{providers::RemoteFile, store::transfers::TransferHandle};
+
+// what happens when a file being downloaded already exists locally
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ConflictPolicy {
+    #[default]
+    Overwrite,
+    Skip,
+    SkipIfSame,
+    Rename,
+    Ask,
+}
+
+impl ConflictPolicy {
+    pub fn next(self) -> Self {
+        match self {
+            ConflictPolicy::Overwrite => ConflictPolicy::Skip,
+            ConflictPolicy::Skip => ConflictPolicy::SkipIfSame,
+            ConflictPolicy::SkipIfSame => ConflictPolicy::Rename,
+            ConflictPolicy::Rename => ConflictPolicy::Ask,
+            ConflictPolicy::Ask => ConflictPolicy::Overwrite,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::Skip => "skip",
+            ConflictPolicy::SkipIfSame => "skip if same size and date",
+            ConflictPolicy::Rename => "rename",
+            ConflictPolicy::Ask => "ask",
+        }
+    }
+
+    // value of `conflict_policy` in the preferences
+    pub fn from_preference(value: &str) -> Option<Self> {
+        match value {
+            "overwrite" => Some(ConflictPolicy::Overwrite),
+            "skip" => Some(ConflictPolicy::Skip),
+            "skip_if_same" => Some(ConflictPolicy::SkipIfSame),
+            "rename" => Some(ConflictPolicy::Rename),
+            "ask" => Some(ConflictPolicy::Ask),
+            _ => None,
+        }
+    }
+}
+
+// how a single conflicting file is handled, either from the policy or the user
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictResolution {
+    Overwrite,
+    Skip,
+    Rename,
+}
+
+// a file waiting for the user to choose how to resolve its conflict
+#[derive(Debug, Clone)]
+pub struct FileConflict {
+    pub transfer_id: usize,
+    pub name: String,
+}
+
+pub fn seconds_since_epoch(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_secs())
+}
+
+// s3 keeps the last modified date with a precision of one second
+pub fn is_same_file(local: &Metadata, remote: &RemoteFile) -> bool {
+    let local_modified = local.modified().ok().and_then(seconds_since_epoch);
+    let remote_modified = remote.last_modified.and_then(seconds_since_epoch);
+    local.len() == remote.size && local_modified.is_some() && local_modified == remote_modified
+}
+
+// local name to write to when the file already exists, None to skip it.
+// same_file tells from the metadata of the local file whether it already holds
+// the content, it is only called for the skip if same policy
+pub async fn resolve<F, Fut>(
+    conflict_policy: ConflictPolicy,
+    name: &str,
+    handle: &TransferHandle,
+    same_file: F,
+) -> Result<Option<String>>
+where
+    F: FnOnce(Metadata) -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let Ok(local) = fs::metadata(name) else {
+        return Ok(Some(name.to_string()));
+    };
+    let resolution = match conflict_policy {
+        ConflictPolicy::Overwrite => ConflictResolution::Overwrite,
+        ConflictPolicy::Skip => ConflictResolution::Skip,
+        ConflictPolicy::SkipIfSame => {
+            if same_file(local).await? {
+                ConflictResolution::Skip
+            } else {
+                ConflictResolution::Overwrite
+            }
+        }
+        ConflictPolicy::Rename => ConflictResolution::Rename,
+        ConflictPolicy::Ask => handle
+            .ask_conflict(name)
+            .await
+            .unwrap_or(ConflictResolution::Skip),
+    };
+    Ok(match resolution {
+        ConflictResolution::Overwrite => Some(name.to_string()),
+        ConflictResolution::Skip => None,
+        ConflictResolution::Rename => Some(renamed_path(name)),
+    })
+}
+
+// first `name (n).ext` that does not exist yet next to the file
+pub fn renamed_path(name: &str) -> String {
+    let path = Path::new(name);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let parent = path.parent().unwrap_or(Path::new(""));
+    (1..)
+        .map(|n| parent.join(format!("{stem} ({n}){extension}")))
+        .find(|candidate| !candidate.exists())
+        .map(|candidate| candidate.to_string_lossy().to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn renames_with_the_first_free_suffix() {
+        let dir = std::env::temp_dir().join(format!("s3li-conflicts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("report.csv"), "").unwrap();
+        fs::write(dir.join("report (1).csv"), "").unwrap();
+
+        let name = dir.join("report.csv").to_string_lossy().to_string();
+        assert_eq!(
+            renamed_path(&name),
+            dir.join("report (2).csv").to_string_lossy().to_string()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use configparser::ini::Ini;
+use dirs::{config_dir, home_dir};
+
+use crate::logger::LOGGER;
+
+const SECTION: &str = "destinations";
+
+// `~` is only expanded by shells, paths typed in the ui need it done by hand
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+// last directory every source has been downloaded to, kept in
+// ~/.config/s3li/destinations.ini so it is remembered across sessions
+#[derive(Debug, Clone, Default)]
+pub struct Destinations {
+    by_source: HashMap<String, String>,
+}
+
+impl Destinations {
+    fn file() -> Option<PathBuf> {
+        config_dir().map(|config_dir| config_dir.join("s3li").join("destinations.ini"))
+    }
+
+    pub fn load() -> Self {
+        let Some(file) = Self::file().filter(|file| file.exists()) else {
+            return Self::default();
+        };
+        let mut ini = Ini::new_cs();
+        if let Err(e) = ini.load(file) {
+            let _ = LOGGER.info(&format!("failed to load destinations {e}"));
+            return Self::default();
+        }
+        let by_source = ini
+            .get_map_ref()
+            .get(SECTION)
+            .map(|destinations| {
+                destinations
+                    .iter()
+                    .filter_map(|(source, destination)| {
+                        Some((source.clone(), destination.clone()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self { by_source }
+    }
+
+    pub fn get(&self, source: &str) -> Option<&String> {
+        self.by_source.get(source)
+    }
+
+    pub fn remember(&mut self, source: &str, destination: &str) {
+        if self.get(source).map(String::as_str) == Some(destination) {
+            return;
+        }
+        self.by_source
+            .insert(source.to_string(), destination.to_string());
+        if let Err(e) = self.save() {
+            let _ = LOGGER.info(&format!("failed to save destinations {e}"));
+        }
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(file) = Self::file() else {
+            return Ok(());
+        };
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut ini = Ini::new_cs();
+        for (source, destination) in &self.by_source {
+            ini.set(SECTION, source, Some(destination.clone()));
+        }
+        ini.write(file)
+    }
+}
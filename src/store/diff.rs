@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::store::sync::{files_by_path, same_content, SyncEntry, SyncLocation};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    OnlyLeft,
+    OnlyRight,
+    // same path with a different size or etag
+    Changed,
+    // same path and size, the etags can't tell whether the content is the same,
+    // e.g. a multipart upload against a single part one
+    Unverified,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    // path relative to both locations
+    pub path: String,
+    pub kind: DiffKind,
+    pub left_size: Option<u64>,
+    pub right_size: Option<u64>,
+}
+
+// read only comparison of two locations, nothing is planned from it
+#[derive(Debug, Clone, Default)]
+pub struct Diff {
+    pub entries: Vec<DiffEntry>,
+    // files whose content is known to be identical
+    pub same: usize,
+    // keys that can't be mapped to a local path, with the reason
+    pub rejected: Vec<(String, String)>,
+}
+
+impl Diff {
+    // number of entries and bytes of a kind, the bytes of the left side for changed entries
+    pub fn total(&self, kind: DiffKind) -> (usize, u64) {
+        self.entries.iter().filter(|entry| entry.kind == kind).fold(
+            (0, 0),
+            |(count, bytes), entry| {
+                (
+                    count + 1,
+                    bytes + entry.left_size.or(entry.right_size).unwrap_or_default(),
+                )
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffResult {
+    pub left: SyncLocation,
+    pub right: SyncLocation,
+    pub diff: Result<Arc<Diff>, String>,
+}
+
+// files are matched by path like in a sync, only the size and the etags are
+// compared: dates say nothing about the content
+pub fn diff(
+    left: &SyncLocation,
+    left_files: Vec<SyncEntry>,
+    right: &SyncLocation,
+    right_files: Vec<SyncEntry>,
+    to_local_path: impl Fn(&str) -> Result<String>,
+) -> Result<Diff> {
+    let mut diff = Diff::default();
+    let left_by_path = files_by_path(left, right, left_files, &to_local_path, &mut diff.rejected);
+    let mut right_by_path =
+        files_by_path(right, left, right_files, &to_local_path, &mut diff.rejected);
+    for (path, left_file) in left_by_path {
+        let Some(right_file) = right_by_path.remove(&path) else {
+            diff.entries.push(DiffEntry {
+                path,
+                kind: DiffKind::OnlyLeft,
+                left_size: Some(left_file.size),
+                right_size: None,
+            });
+            continue;
+        };
+        let kind = if left_file.size != right_file.size {
+            Some(DiffKind::Changed)
+        } else {
+            match same_content((left, &left_file), (right, &right_file))? {
+                Some(true) => None,
+                Some(false) => Some(DiffKind::Changed),
+                None => Some(DiffKind::Unverified),
+            }
+        };
+        match kind {
+            Some(kind) => diff.entries.push(DiffEntry {
+                path,
+                kind,
+                left_size: Some(left_file.size),
+                right_size: Some(right_file.size),
+            }),
+            None => diff.same += 1,
+        }
+    }
+    diff.entries.extend(
+        right_by_path
+            .into_iter()
+            .map(|(path, right_file)| DiffEntry {
+                path,
+                kind: DiffKind::OnlyRight,
+                left_size: None,
+                right_size: Some(right_file.size),
+            }),
+    );
+    diff.entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_added_removed_and_changed_keys() {
+        let left = SyncLocation::parse("s3://bucket/a");
+        let right = SyncLocation::parse("s3://other/b");
+        let diff = diff(
+            &left,
+            vec![
+                SyncEntry::object("same", 1, "\"x\""),
+                SyncEntry::object("changed", 1, "\"x\""),
+                SyncEntry::object("resized", 1, "\"x\""),
+                SyncEntry::object("multipart", 1, "\"x-2\""),
+                SyncEntry::object("left", 3, "\"x\""),
+            ],
+            &right,
+            vec![
+                SyncEntry::object("same", 1, "\"x\""),
+                SyncEntry::object("changed", 1, "\"y\""),
+                SyncEntry::object("resized", 2, "\"x\""),
+                SyncEntry::object("multipart", 1, "\"y\""),
+                SyncEntry::object("right", 4, "\"x\""),
+            ],
+            |key| Ok(key.to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            diff.entries
+                .iter()
+                .map(|entry| (entry.path.as_str(), entry.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("changed", DiffKind::Changed),
+                ("left", DiffKind::OnlyLeft),
+                ("multipart", DiffKind::Unverified),
+                ("resized", DiffKind::Changed),
+                ("right", DiffKind::OnlyRight),
+            ]
+        );
+        assert_eq!(diff.same, 1);
+        assert_eq!(diff.total(DiffKind::OnlyRight), (1, 4));
+    }
+}
@@ -2,22 +2,19 @@ mod tree;
 use anyhow::{anyhow, Result};
 pub use tree::{File, FileTree, Folder, TreeItem};
 
-use std::sync::Arc;
-
-use tokio::sync::Mutex as TokioMutex;
 use tree::TreeNode;
 
-use crate::providers::{AwsClient, ProviderClient};
+use crate::providers::{ProviderClient, SharedClient};
 
 #[derive(Debug, Clone)]
 pub struct Explorer {
     pub selected_folder: Option<Folder>,
     pub file_tree: FileTree,
-    client: Arc<TokioMutex<AwsClient>>,
+    client: SharedClient,
 }
 
 impl Explorer {
-    pub fn new(client: Arc<TokioMutex<AwsClient>>) -> Self {
+    pub fn new(client: SharedClient) -> Self {
         Self {
             selected_folder: None,
             file_tree: FileTree::new(
@@ -36,13 +33,10 @@ impl Explorer {
         folders: Vec<Folder>,
     ) -> Option<TreeNode> {
         let current_node = &self.file_tree.search(current_folder);
-        match current_node {
-            Some(current_node) => {
-                let mut current_node = current_node.lock().unwrap();
-                current_node.files = current_folder_files;
-                current_node.children = folders.into_iter().map(|folder| folder.into()).collect();
-            }
-            None => {}
+        if let Some(current_node) = current_node {
+            let mut current_node = current_node.lock().unwrap();
+            current_node.files = current_folder_files;
+            current_node.children = folders.into_iter().map(|folder| folder.into()).collect();
         };
         current_node.clone()
     }
@@ -50,8 +44,7 @@ impl Explorer {
     pub async fn create_file_tree(&mut self, bucket: &str) -> Result<bool> {
         let (files, folders) = self
             .client
-            .lock()
-            .await
+            .snapshot()
             .list_objects_in_folder(bucket, None)
             .await?;
         let file_tree = FileTree::new(
@@ -87,8 +80,7 @@ impl Explorer {
 
         let (files, folders) = self
             .client
-            .lock()
-            .await
+            .snapshot()
             .list_objects_in_folder(
                 bucket,
                 new_selected_folder
@@ -13,7 +13,7 @@ impl FromStr for File {
         let full_name_split = s.split('/').collect::<Vec<&str>>();
         let relative_name = full_name_split.last().unwrap_or(&"/").to_string();
         let depth = full_name_split.len();
-        let depth = if depth > 1 { depth - 1 } else { 0 };
+        let depth = depth.saturating_sub(1);
         Ok(Self {
             name: s.to_string(),
             relative_name,
@@ -57,10 +57,7 @@ impl FileTree {
         }
     }
 
-    pub fn get_root(&self) -> &TreeNode {
-        &self.root
-    }
-
+    #[cfg(test)]
     pub fn insert(self, node: Node, folder_to_find: Folder) -> Self {
         let parent_node = search_tree(self.root.clone(), &folder_to_find);
         if let Some(parent_node) = parent_node {
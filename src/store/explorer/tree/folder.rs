@@ -15,7 +15,7 @@ impl FromStr for Folder {
             // this is a folder
             let full_name_split = s.split('/').collect::<Vec<&str>>();
             let depth = full_name_split.len();
-            let depth = if depth > 2 { depth - 2 } else { 0 };
+            let depth = depth.saturating_sub(2);
             let folder_relative_name = match full_name_split[..] {
                 [.., relative_name_item, _] => relative_name_item.to_string(),
                 _ => {
@@ -26,7 +26,7 @@ impl FromStr for Folder {
             (folder_relative_name, depth)
         } else {
             // this is a file
-            (s.split('/').last().unwrap_or(s).to_string(), 0)
+            (s.split('/').next_back().unwrap_or(s).to_string(), 0)
         };
         Ok(Self {
             name: s.to_string(),
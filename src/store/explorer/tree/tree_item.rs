@@ -14,16 +14,10 @@ impl TreeItem {
         }
     }
 
-    pub fn pop_name_char(&mut self) {
+    pub fn set_name(&mut self, name: String) {
         match self {
-            TreeItem::Folder(folder, _) => folder.relative_name.pop(),
-            TreeItem::File(file, _) => file.relative_name.pop(),
-        };
-    }
-    pub fn push_name_char(&mut self, val: char) {
-        match self {
-            TreeItem::Folder(folder, _) => folder.relative_name.push(val),
-            TreeItem::File(file, _) => file.relative_name.push(val),
+            TreeItem::Folder(folder, _) => folder.relative_name = name,
+            TreeItem::File(file, _) => file.relative_name = name,
         };
     }
 }
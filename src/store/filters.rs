@@ -0,0 +1,119 @@
+use anyhow::{Context, Result};
+use regex_lite::Regex;
+
+#[derive(Debug, Clone)]
+struct Pattern {
+    regex: Regex,
+    // globs without a slash are matched against the file name at any depth
+    file_name_only: bool,
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Result<Self> {
+        if let Some(regex) = pattern.strip_prefix("re:") {
+            return Ok(Self {
+                regex: Regex::new(regex).with_context(|| format!("Invalid regex {regex}"))?,
+                file_name_only: false,
+            });
+        }
+        Ok(Self {
+            regex: Regex::new(&glob_to_regex(pattern))
+                .with_context(|| format!("Invalid glob {pattern}"))?,
+            file_name_only: !pattern.contains('/'),
+        })
+    }
+
+    fn matches(&self, relative_key: &str) -> bool {
+        if self.file_name_only {
+            let file_name = relative_key.rsplit('/').next().unwrap_or(relative_key);
+            self.regex.is_match(file_name)
+        } else {
+            self.regex.is_match(relative_key)
+        }
+    }
+}
+
+// `*` and `?` stay inside a folder, `**` crosses them
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(char) = chars.next() {
+        match char {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            char => regex.push_str(&regex_lite::escape(&char.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+// include and exclude patterns of folder downloads, matched against the keys
+// relative to the selected folder. patterns are separated by spaces, `re:`
+// starts a regex, anything else is a glob
+#[derive(Debug, Clone, Default)]
+pub struct KeyFilters {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl KeyFilters {
+    pub fn parse(include: &str, exclude: &str) -> Result<Self> {
+        let parse_all = |patterns: &str| {
+            patterns
+                .split_whitespace()
+                .map(Pattern::parse)
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            include: parse_all(include)?,
+            exclude: parse_all(exclude)?,
+        })
+    }
+
+    // without include patterns every key is included
+    pub fn matches(&self, relative_key: &str) -> bool {
+        (self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches(relative_key)))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| pattern.matches(relative_key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_globs_and_regexes() {
+        let filters = KeyFilters::parse("*.parquet date=2024-*/*", "re:_tmp").unwrap();
+        assert!(filters.matches("part-0.parquet"));
+        assert!(filters.matches("date=2023-12/nested/part-0.parquet"));
+        assert!(filters.matches("date=2024-01/part-0.csv"));
+        assert!(!filters.matches("date=2024-01/nested/part-0.csv"));
+        assert!(!filters.matches("date=2023-12/part-0.csv"));
+        assert!(!filters.matches("date=2024-01/part_tmp.parquet"));
+
+        let filters = KeyFilters::parse("logs/**/*.gz", "").unwrap();
+        assert!(filters.matches("logs/a.gz"));
+        assert!(filters.matches("logs/2024/01/a.gz"));
+        assert!(!filters.matches("other/a.gz"));
+
+        assert!(KeyFilters::parse("", "").unwrap().matches("anything"));
+        assert!(KeyFilters::parse("re:(", "").is_err());
+    }
+}
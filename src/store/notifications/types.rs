@@ -1,8 +1,12 @@
+use crate::store::sources::traits::FailedDownloads;
+
 #[derive(Default, Debug, Clone)]
 pub struct Notification {
     message: String,
     error: bool,
     shown: bool,
+    // files the alert offers to download again
+    retry: Option<FailedDownloads>,
 }
 
 impl Notification {
@@ -18,6 +22,9 @@ impl Notification {
     pub fn set_shown(&mut self) {
         self.shown = true
     }
+    pub fn get_retry(&self) -> Option<&FailedDownloads> {
+        self.retry.as_ref()
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -32,6 +39,7 @@ impl Notifications {
                 message,
                 error,
                 shown: true,
+                retry: None,
             }));
     }
 
@@ -41,6 +49,17 @@ impl Notifications {
                 message,
                 error: true,
                 shown: false,
+                retry: None,
+            }));
+    }
+
+    pub fn push_alert_with_retry(&mut self, message: String, retry: FailedDownloads) {
+        self.notifications
+            .push(NotificationType::Alert(Notification {
+                message,
+                error: true,
+                shown: false,
+                retry: Some(retry),
             }));
     }
 
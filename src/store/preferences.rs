@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+use configparser::ini::Ini;
+use dirs::config_dir;
+
+use crate::{logger::LOGGER, providers::DownloadOptions};
+
+use super::conflicts::ConflictPolicy;
+
+const DEFAULT_MAX_CONCURRENT_TRANSFERS: usize = 8;
+const MIB: u64 = 1024 * 1024;
+
+// application wide settings read from ~/.config/s3li/preferences.ini
+#[derive(Debug, Clone)]
+pub struct Preferences {
+    pub max_concurrent_transfers: usize,
+    pub download_options: DownloadOptions,
+    // preselected in the download popup
+    pub conflict_policy: ConflictPolicy,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            max_concurrent_transfers: DEFAULT_MAX_CONCURRENT_TRANSFERS,
+            download_options: DownloadOptions::default(),
+            conflict_policy: ConflictPolicy::default(),
+        }
+    }
+}
+
+// zero is never a meaningful value for the numeric preferences
+fn get_positive(ini: &Ini, section: &str, key: &str) -> Option<u64> {
+    ini.getuint(section, key)
+        .ok()
+        .flatten()
+        .filter(|value| *value > 0)
+}
+
+impl Preferences {
+    fn file() -> Option<PathBuf> {
+        config_dir().map(|config_dir| config_dir.join("s3li").join("preferences.ini"))
+    }
+
+    // a missing or invalid file falls back to the defaults
+    pub fn load() -> Self {
+        let Some(file) = Self::file().filter(|file| file.exists()) else {
+            return Self::default();
+        };
+        let mut ini = Ini::new();
+        match ini.load(file) {
+            Ok(_) => Self::from_ini(&ini),
+            Err(e) => {
+                let _ = LOGGER.info(&format!("failed to load preferences {e}"));
+                Self::default()
+            }
+        }
+    }
+
+    fn from_ini(ini: &Ini) -> Self {
+        let defaults = Self::default();
+        let max_concurrent_transfers = get_positive(ini, "transfers", "max_concurrent")
+            .and_then(|value| usize::try_from(value).ok())
+            .unwrap_or(defaults.max_concurrent_transfers);
+        let download_options = DownloadOptions {
+            ranged_threshold: get_positive(ini, "transfers", "ranged_threshold_mb")
+                .map(|value| value * MIB)
+                .unwrap_or(defaults.download_options.ranged_threshold),
+            part_size: get_positive(ini, "transfers", "part_size_mb")
+                .map(|value| value * MIB)
+                .unwrap_or(defaults.download_options.part_size),
+            max_parallel_parts: get_positive(ini, "transfers", "parallel_parts")
+                .and_then(|value| usize::try_from(value).ok())
+                .unwrap_or(defaults.download_options.max_parallel_parts),
+            preserve_modified_time: ini
+                .getbool("transfers", "preserve_modified_time")
+                .ok()
+                .flatten()
+                .unwrap_or(defaults.download_options.preserve_modified_time),
+            max_attempts: get_positive(ini, "transfers", "download_attempts")
+                .and_then(|value| u32::try_from(value).ok())
+                .unwrap_or(defaults.download_options.max_attempts),
+        };
+        let conflict_policy = ini
+            .get("transfers", "conflict_policy")
+            .and_then(|value| ConflictPolicy::from_preference(&value))
+            .unwrap_or(defaults.conflict_policy);
+        Self {
+            max_concurrent_transfers,
+            download_options,
+            conflict_policy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_preferences_falling_back_to_defaults() {
+        let mut ini = Ini::new();
+        ini.read(
+            "[transfers]\nmax_concurrent = 3\npart_size_mb = 5\nconflict_policy = skip_if_same\npreserve_modified_time = false\ndownload_attempts = 5"
+                .to_string(),
+        )
+        .unwrap();
+        let preferences = Preferences::from_ini(&ini);
+        assert_eq!(preferences.max_concurrent_transfers, 3);
+        assert_eq!(preferences.download_options.part_size, 5 * MIB);
+        assert_eq!(preferences.conflict_policy, ConflictPolicy::SkipIfSame);
+        assert!(!preferences.download_options.preserve_modified_time);
+        assert_eq!(preferences.download_options.max_attempts, 5);
+        assert_eq!(
+            preferences.download_options.max_parallel_parts,
+            DownloadOptions::default().max_parallel_parts
+        );
+
+        let mut ini = Ini::new();
+        ini.read("[transfers]\nmax_concurrent = 0".to_string())
+            .unwrap();
+        assert_eq!(
+            Preferences::from_ini(&ini).max_concurrent_transfers,
+            DEFAULT_MAX_CONCURRENT_TRANSFERS
+        );
+    }
+}
@@ -1,7 +1,15 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use buckets::Buckets;
 pub use traits::WithSources;
-use traits::{DownloadResult, Downloadable};
+use traits::{DownloadResult, DownloadSettings, Downloadable, FailedDownloads, FolderObject};
+
+use super::{
+    diff::Diff,
+    sync::{SyncLocation, SyncPlan, SyncRequest, SyncResult},
+    transfers::TransferReporter,
+};
 
 pub mod buckets;
 pub mod traits;
@@ -30,6 +38,12 @@ impl WithSources for Sources {
         }
     }
 
+    fn get_sources_regions(&self) -> HashMap<String, String> {
+        match self {
+            Sources::Buckets(buckets) => buckets.get_sources_regions(),
+        }
+    }
+
     async fn update_available_sources(&mut self) -> Result<&Vec<String>> {
         match self {
             Sources::Buckets(buckets) => buckets.update_available_sources().await,
@@ -38,9 +52,51 @@ impl WithSources for Sources {
 }
 
 impl Sources {
-    pub async fn download(&self, items: Vec<impl Downloadable>) -> Result<DownloadResult> {
+    pub async fn download(
+        &self,
+        items: Vec<impl Downloadable>,
+        transfers: TransferReporter,
+        settings: DownloadSettings,
+    ) -> Result<DownloadResult> {
+        match self {
+            Sources::Buckets(buckets) => buckets.download(items, transfers, settings).await,
+        }
+    }
+
+    pub async fn retry(
+        &self,
+        failed: FailedDownloads,
+        transfers: TransferReporter,
+    ) -> Result<DownloadResult> {
+        match self {
+            Sources::Buckets(buckets) => buckets.retry(failed, transfers).await,
+        }
+    }
+
+    pub async fn list_folder_objects(
+        &self,
+        items: Vec<impl Downloadable>,
+    ) -> Result<Vec<FolderObject>> {
+        match self {
+            Sources::Buckets(buckets) => buckets.list_folder_objects(items).await,
+        }
+    }
+
+    pub async fn diff(&self, left: SyncLocation, right: SyncLocation) -> Result<Diff> {
+        match self {
+            Sources::Buckets(buckets) => buckets.diff(left, right).await,
+        }
+    }
+
+    pub async fn plan_sync(&self, request: SyncRequest) -> Result<SyncPlan> {
+        match self {
+            Sources::Buckets(buckets) => buckets.plan_sync(request).await,
+        }
+    }
+
+    pub async fn sync(&self, plan: &SyncPlan, transfers: TransferReporter) -> Result<SyncResult> {
         match self {
-            Sources::Buckets(buckets) => buckets.download(items).await,
+            Sources::Buckets(buckets) => buckets.sync(plan, transfers).await,
         }
     }
 }
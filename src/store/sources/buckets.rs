@@ -1,23 +1,44 @@
 pub mod entities;
+mod local_path;
 
-use std::sync::Arc;
+use std::{collections::HashMap, path::Path};
 
 use anyhow::Result;
-use tokio::sync::Mutex;
 
-use crate::providers::{AwsClient, ProviderClient};
+use crate::{
+    providers::{ProviderClient, SharedClient},
+    store::{
+        archives::ArchiveSettings,
+        conflicts::ConflictPolicy,
+        destinations::expand_home,
+        diff::{self, Diff},
+        sync::{self, SyncAction, SyncEntry, SyncLocation, SyncPlan, SyncRequest, SyncResult},
+        transfers::TransferReporter,
+    },
+};
 
-use super::traits::{DownloadResult, Downloadable, WithSources};
+use self::{
+    entities::{
+        delete_files, download_files, upload_files, BucketArchive, BucketDeletion, BucketFile,
+        BucketUpload, UploadOrigin,
+    },
+    local_path::to_local_path,
+};
+
+use super::traits::{
+    DownloadResult, DownloadResultStatus, DownloadSettings, Downloadable, FailedDownloads,
+    FolderObject, WithSources,
+};
 
 #[derive(Debug, Clone)]
 pub struct Buckets {
     available_sources: Vec<String>,
     active_source: Option<String>,
-    client: Arc<Mutex<AwsClient>>,
+    client: SharedClient,
 }
 
 impl Buckets {
-    pub fn new(client: Arc<Mutex<AwsClient>>) -> Self {
+    pub fn new(client: SharedClient) -> Self {
         Self {
             available_sources: vec![],
             active_source: None,
@@ -40,24 +61,252 @@ impl WithSources for Buckets {
         &self.available_sources
     }
 
+    fn get_sources_regions(&self) -> HashMap<String, String> {
+        self.client.snapshot().bucket_regions()
+    }
+
     async fn update_available_sources(&mut self) -> Result<&Vec<String>> {
-        let sources = self.client.lock().await.list_buckets().await?;
+        let sources = self.client.snapshot().list_buckets().await?;
         self.available_sources = sources;
         Ok(&self.available_sources)
     }
 }
 
 impl Buckets {
-    pub async fn download(&self, items: Vec<impl Downloadable>) -> Result<DownloadResult> {
-        let mut result = DownloadResult::default();
+    pub async fn download(
+        &self,
+        items: Vec<impl Downloadable>,
+        transfers: TransferReporter,
+        settings: DownloadSettings,
+    ) -> Result<DownloadResult> {
+        if let Some(archive) = &settings.archive {
+            return self
+                .download_archive(items, transfers, &settings, archive)
+                .await;
+        }
+        let client = (*self.client.snapshot()).clone();
+        let source = self.active_source.clone().unwrap();
+        let (files, rejected) =
+            Self::list_selection(items, client.clone(), &source, &settings).await?;
+        let destination = expand_home(&settings.destination);
+        let files = files
+            .into_iter()
+            .map(|(key, path)| {
+                BucketFile::new(key, destination.join(path).to_string_lossy().to_string())
+            })
+            .collect();
+        // the whole selection shares the same queue of transfers
+        let result =
+            download_files(files, client, source, transfers, settings.conflict_policy).await?;
+        Ok(rejected.merge_results(result))
+    }
+
+    // keys of the files of all the selected items with their path relative to the
+    // destination, the keys that can't be saved locally are reported as rejected
+    async fn list_selection(
+        items: Vec<impl Downloadable>,
+        client: impl ProviderClient + Clone + 'static,
+        source: &str,
+        settings: &DownloadSettings,
+    ) -> Result<(Vec<(String, String)>, DownloadResult)> {
+        let mut rejected = DownloadResult::default();
+        let mut files = vec![];
+        for item in items {
+            for (key, path) in item
+                .list_files(client.clone(), source.to_string(), &settings.filters)
+                .await?
+            {
+                match path {
+                    Ok(path) => files.push((key, path)),
+                    Err(e) => rejected.append_rejected(key, format!("{e:#}")),
+                }
+            }
+        }
+        Ok((files, rejected))
+    }
+
+    async fn download_archive(
+        &self,
+        items: Vec<impl Downloadable>,
+        transfers: TransferReporter,
+        settings: &DownloadSettings,
+        archive: &ArchiveSettings,
+    ) -> Result<DownloadResult> {
+        let client = (*self.client.snapshot()).clone();
+        let source = self.active_source.clone().unwrap();
+        let (files, rejected) =
+            Self::list_selection(items, client.clone(), &source, settings).await?;
+        let name = expand_home(&settings.destination)
+            .join(&archive.name)
+            .to_string_lossy()
+            .to_string();
+        let result = BucketArchive::new(name, archive.format, files)
+            .download(client, source, transfers, settings.conflict_policy)
+            .await?;
+        Ok(rejected.merge_results(result))
+    }
+
+    // the failed files are downloaded again from the source they came from, even
+    // when another source has been selected since
+    pub async fn retry(
+        &self,
+        failed: FailedDownloads,
+        transfers: TransferReporter,
+    ) -> Result<DownloadResult> {
+        let files = failed
+            .files
+            .into_iter()
+            .map(|(key, name)| BucketFile::new(key, name))
+            .collect();
+        download_files(
+            files,
+            (*self.client.snapshot()).clone(),
+            failed.source,
+            transfers,
+            failed.conflict_policy,
+        )
+        .await
+    }
+
+    pub async fn list_folder_objects(
+        &self,
+        items: Vec<impl Downloadable>,
+    ) -> Result<Vec<FolderObject>> {
+        let client = (*self.client.snapshot()).clone();
+        let source = self.active_source.clone().unwrap();
+        let mut objects = vec![];
         for item in items {
-            let download_result = item
-                .download(
-                    self.client.lock().await.clone(),
-                    self.active_source.clone().unwrap(),
+            objects.extend(
+                item.list_folder_objects(client.clone(), source.clone())
+                    .await?,
+            );
+        }
+        Ok(objects)
+    }
+
+    async fn list_sync_entries(
+        client: impl ProviderClient,
+        location: &SyncLocation,
+    ) -> Result<Vec<SyncEntry>> {
+        match location {
+            SyncLocation::Local(dir) => {
+                let dir = dir.clone();
+                tokio::task::spawn_blocking(move || sync::list_local_files(Path::new(&dir))).await?
+            }
+            SyncLocation::Folder { source, prefix } => Ok(client
+                .list_objects(source, prefix)
+                .await?
+                .into_iter()
+                // keys ending with a slash are placeholders for empty folders
+                .filter(|(key, _)| !key.ends_with('/'))
+                .map(|(key, remote_file)| {
+                    let relative_key = key.strip_prefix(prefix.as_str()).unwrap_or(&key);
+                    SyncEntry::remote(relative_key.to_string(), remote_file)
+                })
+                .collect()),
+        }
+    }
+
+    fn expand_local(location: SyncLocation) -> SyncLocation {
+        match location {
+            SyncLocation::Local(dir) => {
+                SyncLocation::Local(expand_home(&dir).to_string_lossy().to_string())
+            }
+            location => location,
+        }
+    }
+
+    pub async fn diff(&self, left: SyncLocation, right: SyncLocation) -> Result<Diff> {
+        let client = (*self.client.snapshot()).clone();
+        let (left, right) = (Self::expand_local(left), Self::expand_local(right));
+        let left_files = Self::list_sync_entries(client.clone(), &left).await?;
+        let right_files = Self::list_sync_entries(client, &right).await?;
+        tokio::task::spawn_blocking(move || {
+            diff::diff(&left, left_files, &right, right_files, to_local_path)
+        })
+        .await?
+    }
+
+    // both sides are listed and compared without changing anything, local files
+    // are hashed when the etags allow it
+    pub async fn plan_sync(&self, request: SyncRequest) -> Result<SyncPlan> {
+        let client = (*self.client.snapshot()).clone();
+        let request = SyncRequest {
+            target: Self::expand_local(request.target),
+            ..request
+        };
+        let (from, to) = request.sides();
+        let from_files = Self::list_sync_entries(client.clone(), &from).await?;
+        let to_files = Self::list_sync_entries(client, &to).await?;
+        tokio::task::spawn_blocking(move || {
+            sync::plan(&request, from_files, to_files, to_local_path)
+        })
+        .await?
+    }
+
+    // transfers run first, deletions only once everything has been copied: a
+    // cancelled or failed copy leaves both sides different and nothing is deleted
+    pub async fn sync(&self, plan: &SyncPlan, transfers: TransferReporter) -> Result<SyncResult> {
+        let client = (*self.client.snapshot()).clone();
+        let (mut downloads, mut uploads, mut deletions) = (vec![], vec![], vec![]);
+        for action in &plan.actions {
+            match action {
+                SyncAction::Download { key, path } => {
+                    downloads.push(BucketFile::new(key.clone(), path.clone()))
+                }
+                SyncAction::Upload { path, key } => uploads.push(BucketUpload::new(
+                    UploadOrigin::File(path.clone()),
+                    plan.source.clone(),
+                    key.clone(),
+                )),
+                SyncAction::Copy {
+                    source,
+                    key,
+                    to_source,
+                    to_key,
+                } => uploads.push(BucketUpload::new(
+                    UploadOrigin::Object {
+                        source: source.clone(),
+                        key: key.clone(),
+                    },
+                    to_source.clone(),
+                    to_key.clone(),
+                )),
+                SyncAction::DeleteLocal { path } => {
+                    deletions.push(BucketDeletion::Local(path.clone()))
+                }
+                SyncAction::DeleteRemote { source, key } => {
+                    deletions.push(BucketDeletion::Remote {
+                        source: source.clone(),
+                        key: key.clone(),
+                    })
+                }
+            }
+        }
+        let mut result = SyncResult {
+            downloads: download_files(
+                downloads,
+                client.clone(),
+                plan.source.clone(),
+                transfers.clone(),
+                ConflictPolicy::Overwrite,
+            )
+            .await?,
+            uploads: upload_files(uploads, client.clone(), transfers.clone()).await?,
+            ..Default::default()
+        };
+        let copied = [&result.downloads.status, &result.uploads.status]
+            .into_iter()
+            .all(|status| {
+                matches!(
+                    status,
+                    DownloadResultStatus::Successful | DownloadResultStatus::Pending
                 )
-                .await?;
-            result = result.merge_results(download_result);
+            });
+        if copied {
+            result.deletions = delete_files(deletions, client, transfers).await?;
+        } else {
+            result.skipped_deletions = deletions.len();
         }
         Ok(result)
     }
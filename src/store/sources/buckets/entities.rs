@@ -1,14 +1,21 @@
+use std::{fs, future::Future};
+
 use anyhow::{Context, Result};
-use futures::future::join_all;
 
 use crate::{
-    providers::ProviderClient,
+    providers::{PartialDownload, ProviderClient, RemoteFile},
     store::{
+        archives::{ArchiveFormat, ArchiveWriter},
+        conflicts::{is_same_file, resolve, ConflictPolicy},
         explorer::{File, Folder, TreeItem},
-        sources::traits::{DownloadResult, Downloadable},
+        filters::KeyFilters,
+        sources::traits::{DownloadResult, Downloadable, FolderObject},
+        transfers::{TransferHandle, TransferReporter},
     },
 };
 
+use super::local_path::to_local_path;
+
 #[derive(Clone)]
 pub struct BucketFile {
     key: String,
@@ -32,6 +39,105 @@ impl BucketFile {
             name: file_name,
         }
     }
+
+    // local name to download to when the file already exists, None to skip it
+    async fn resolve_conflict(
+        &self,
+        client: impl ProviderClient,
+        source: &str,
+        handle: &TransferHandle,
+        conflict_policy: ConflictPolicy,
+    ) -> Result<Option<String>> {
+        resolve(conflict_policy, &self.name, handle, |local| async move {
+            let remote = client.file_metadata(source, &self.key).await?;
+            Ok(is_same_file(&local, &remote))
+        })
+        .await
+    }
+
+    async fn download_with_handle(
+        &self,
+        client: impl ProviderClient + Clone + 'static,
+        source: String,
+        mut handle: TransferHandle,
+        conflict_policy: ConflictPolicy,
+    ) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        let token = handle.cancellation_token();
+        // nothing has been written yet, the local file must be left untouched
+        if token.is_cancelled() {
+            handle.cancelled();
+            result.append_cancelled(self.key.clone());
+            return result;
+        }
+        let resolved = tokio::select! {
+            biased;
+            _ = token.cancelled() => None,
+            resolved = self.resolve_conflict(client.clone(), &source, &handle, conflict_policy) => Some(resolved),
+        };
+        let name = match resolved {
+            None => {
+                handle.cancelled();
+                result.append_cancelled(self.key.clone());
+                return result;
+            }
+            Some(Ok(None)) => {
+                handle.skipped();
+                result.append_skipped(self.key.clone());
+                return result;
+            }
+            Some(Ok(Some(name))) => name,
+            Some(Err(e)) => {
+                handle.fail(format!("{:#}", e));
+                result.append_failed(self.key.clone(), self.name.clone(), e);
+                return result;
+            }
+        };
+        let mut started = false;
+        let download =
+            client.download_file(&source, &self.key, &name, |bytes_done, total_bytes| {
+                if !started {
+                    started = true;
+                    handle.start(total_bytes);
+                }
+                handle.progress(bytes_done);
+            });
+        let download_result = tokio::select! {
+            biased;
+            _ = token.cancelled() => None,
+            download_result = download => Some(download_result),
+        };
+        let Some(download_result) = download_result else {
+            // a cancelled download is not meant to be resumed
+            PartialDownload::new(&name).discard();
+            handle.cancelled();
+            result.append_cancelled(self.key.clone());
+            return result;
+        };
+        let download_result = download_result.with_context(|| {
+            format!(
+                "File with key {} and name {} failed to download",
+                self.key, name
+            )
+        });
+        match download_result {
+            Ok(downloaded) => {
+                handle.complete();
+                if name != self.name {
+                    result.renamed.push((self.key.clone(), name.clone()));
+                }
+                result.append_to_result(self.key.clone(), Ok(downloaded));
+            }
+            Err(e) => {
+                // the local file was never touched, only the temporary part remains
+                PartialDownload::new(&name).discard_if_not_resumable();
+                handle.fail(format!("{:#}", e));
+                // a retry maps the key to the same local name and resolves conflicts again
+                result.append_failed(self.key.clone(), self.name.clone(), e);
+            }
+        }
+        result
+    }
 }
 
 pub struct BucketFolder {
@@ -43,67 +149,367 @@ impl BucketFolder {
     pub fn new(key: String, name: String) -> Self {
         Self { key, name }
     }
+
+    async fn list_objects(
+        &self,
+        client: impl ProviderClient,
+        source: &str,
+    ) -> Result<Vec<(String, RemoteFile)>> {
+        let files_in_folder = client.list_objects(source, &self.key).await?;
+        Ok(files_in_folder
+            .into_iter()
+            // keys ending with a slash are placeholders for empty folders
+            .filter(|(file, _)| !file.ends_with('/'))
+            .collect())
+    }
+
+    fn relative_key<'a>(&self, key: &'a str) -> &'a str {
+        key.strip_prefix(&self.key).unwrap_or(key)
+    }
 }
 
 impl Downloadable for BucketFile {
-    async fn download(
+    async fn list_files(
         &self,
-        client: impl ProviderClient + Clone + 'static,
-        source: String,
-    ) -> Result<DownloadResult> {
-        let mut result = DownloadResult::default();
-        let download_result = client
-            .download_file(&source, &self.key, &self.name)
-            .await
-            .with_context(|| {
-                format!(
-                    "File with key {} and name {} failed to download",
-                    self.key, self.name
-                )
-            });
-        result.append_to_result(self.key.clone(), download_result);
-        Ok(result)
+        _client: impl ProviderClient + Clone + 'static,
+        _source: String,
+        _filters: &KeyFilters,
+    ) -> Result<Vec<(String, Result<String>)>> {
+        Ok(vec![(self.key.clone(), to_local_path(&self.name))])
+    }
+
+    async fn list_folder_objects(
+        &self,
+        _client: impl ProviderClient + Clone + 'static,
+        _source: String,
+    ) -> Result<Vec<FolderObject>> {
+        Ok(vec![])
     }
 }
 
 impl Downloadable for BucketFolder {
-    async fn download(
+    async fn list_files(
         &self,
         client: impl ProviderClient + Clone + 'static,
         source: String,
-    ) -> Result<DownloadResult> {
-        let files_in_folder = client.list_objects(&source, &self.key).await?;
-
-        let files_to_download = files_in_folder
+        filters: &KeyFilters,
+    ) -> Result<Vec<(String, Result<String>)>> {
+        let files_in_folder = self.list_objects(client, &source).await?;
+        Ok(files_in_folder
             .into_iter()
-            .map(|file| BucketFile::from_key(file, &self.key, &self.name))
-            .collect::<Vec<_>>();
+            .filter(|(file, _)| filters.matches(self.relative_key(file)))
+            .map(|(file, _)| {
+                let path =
+                    to_local_path(&BucketFile::from_key(file.clone(), &self.key, &self.name).name);
+                (file, path)
+            })
+            .collect())
+    }
 
-        let operations = files_to_download
+    async fn list_folder_objects(
+        &self,
+        client: impl ProviderClient + Clone + 'static,
+        source: String,
+    ) -> Result<Vec<FolderObject>> {
+        let files_in_folder = self.list_objects(client, &source).await?;
+        Ok(files_in_folder
             .into_iter()
-            .map(|file_to_download| {
-                let client_cloned = client.clone();
-                let source_cloned = source.clone();
-                tokio::task::spawn(async move {
-                    file_to_download
-                        .clone()
-                        .download(client_cloned, source_cloned)
-                        .await
-                })
+            .map(|(file, remote_file)| FolderObject {
+                relative_key: self.relative_key(&file).to_string(),
+                size: remote_file.size,
             })
-            .collect::<Vec<_>>();
+            .collect())
+    }
+}
 
-        let results = join_all(operations).await;
-        let all_results: Result<Vec<DownloadResult>> =
-            results.into_iter().map(|res| res?).collect();
+// files already mapped to their local name, downloaded concurrently
+pub async fn download_files(
+    files: Vec<BucketFile>,
+    client: impl ProviderClient + Clone + 'static,
+    source: String,
+    transfers: TransferReporter,
+    conflict_policy: ConflictPolicy,
+) -> Result<DownloadResult> {
+    Ok(run_transfers(files, &transfers, |file, handle| {
+        let client = client.clone();
+        let source = source.clone();
+        async move {
+            file.download_with_handle(client, source, handle, conflict_policy)
+                .await
+        }
+    })
+    .await)
+}
 
-        all_results.map(|val| {
-            val.into_iter()
-                .fold(DownloadResult::default(), |acc, res| acc.merge_results(res))
-        })
+// where the content of an uploaded object comes from
+#[derive(Clone)]
+pub enum UploadOrigin {
+    File(String),
+    // copied by the provider without going through the client
+    Object { source: String, key: String },
+}
+
+impl UploadOrigin {
+    fn describe(&self) -> String {
+        match self {
+            UploadOrigin::File(path) => path.clone(),
+            UploadOrigin::Object { source, key } => format!("s3://{source}/{key}"),
+        }
     }
 }
 
+// an object written to a key of a bucket, overwriting whatever is there
+#[derive(Clone)]
+pub struct BucketUpload {
+    origin: UploadOrigin,
+    source: String,
+    key: String,
+}
+
+impl BucketUpload {
+    pub fn new(origin: UploadOrigin, source: String, key: String) -> Self {
+        Self {
+            origin,
+            source,
+            key,
+        }
+    }
+
+    async fn upload(
+        &self,
+        client: impl ProviderClient,
+        handle: &mut TransferHandle,
+    ) -> Result<bool> {
+        match &self.origin {
+            UploadOrigin::File(path) => {
+                let mut started = false;
+                client
+                    .upload_file(&self.source, &self.key, path, |bytes_done, total_bytes| {
+                        if !started {
+                            started = true;
+                            handle.start(total_bytes);
+                        }
+                        handle.progress(bytes_done);
+                    })
+                    .await
+            }
+            UploadOrigin::Object { source, key } => {
+                handle.start(None);
+                client
+                    .copy_object(source, key, &self.source, &self.key)
+                    .await
+                    .map(|_| true)
+            }
+        }
+    }
+
+    async fn upload_with_handle(
+        &self,
+        client: impl ProviderClient,
+        mut handle: TransferHandle,
+    ) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        let token = handle.cancellation_token();
+        let upload_result = tokio::select! {
+            biased;
+            _ = token.cancelled() => None,
+            upload_result = self.upload(client, &mut handle) => Some(upload_result),
+        };
+        match upload_result {
+            None => {
+                handle.cancelled();
+                result.append_cancelled(self.key.clone());
+            }
+            Some(Ok(uploaded)) => {
+                handle.complete();
+                result.append_to_result(self.key.clone(), Ok(uploaded));
+            }
+            Some(Err(e)) => {
+                let e = e.context(format!(
+                    "{} failed to upload to key {}",
+                    self.origin.describe(),
+                    self.key
+                ));
+                handle.fail(format!("{:#}", e));
+                result.append_to_result(self.key.clone(), Err(e));
+            }
+        }
+        result
+    }
+}
+
+// uploads and copies run concurrently, sharing the transfers of the downloads
+pub async fn upload_files(
+    files: Vec<BucketUpload>,
+    client: impl ProviderClient + Clone + 'static,
+    transfers: TransferReporter,
+) -> Result<DownloadResult> {
+    Ok(run_transfers(files, &transfers, |file, handle| {
+        let client = client.clone();
+        async move { file.upload_with_handle(client, handle).await }
+    })
+    .await)
+}
+
+// a local file or an object removed by a sync, it is missing from the copied side
+#[derive(Clone)]
+pub enum BucketDeletion {
+    Local(String),
+    Remote { source: String, key: String },
+}
+
+impl BucketDeletion {
+    fn key(&self) -> &str {
+        match self {
+            BucketDeletion::Local(path) => path,
+            BucketDeletion::Remote { key, .. } => key,
+        }
+    }
+
+    async fn delete(&self, client: impl ProviderClient) -> Result<()> {
+        match self {
+            BucketDeletion::Local(path) => Ok(fs::remove_file(path)?),
+            BucketDeletion::Remote { source, key } => client.delete_object(source, key).await,
+        }
+    }
+
+    async fn delete_with_handle(
+        &self,
+        client: impl ProviderClient,
+        mut handle: TransferHandle,
+    ) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        if handle.cancellation_token().is_cancelled() {
+            handle.cancelled();
+            result.append_cancelled(self.key().to_string());
+            return result;
+        }
+        handle.start(None);
+        match self.delete(client).await {
+            Ok(_) => {
+                handle.complete();
+                result.append_to_result(self.key().to_string(), Ok(true));
+            }
+            Err(e) => {
+                let e = e.context(format!("{} failed to be deleted", self.key()));
+                handle.fail(format!("{:#}", e));
+                result.append_to_result(self.key().to_string(), Err(e));
+            }
+        }
+        result
+    }
+}
+
+// deletions show up in the transfers like any other change made by a sync
+pub async fn delete_files(
+    deletions: Vec<BucketDeletion>,
+    client: impl ProviderClient + Clone + 'static,
+    transfers: TransferReporter,
+) -> Result<DownloadResult> {
+    Ok(run_transfers(deletions, &transfers, |deletion, handle| {
+        let client = client.clone();
+        async move { deletion.delete_with_handle(client, handle).await }
+    })
+    .await)
+}
+
+// an item moved by a single transfer
+trait TransferItem {
+    // name shown in the transfers panel
+    fn transfer_name(&self) -> &str;
+    // result of the item when its transfer stopped before reporting anything
+    fn interrupted(&self, error: anyhow::Error) -> DownloadResult;
+    // result of the item when every transfer was cancelled before it started
+    fn cancelled(&self) -> DownloadResult;
+}
+
+impl TransferItem for BucketFile {
+    fn transfer_name(&self) -> &str {
+        &self.name
+    }
+
+    fn interrupted(&self, error: anyhow::Error) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        result.append_failed(self.key.clone(), self.name.clone(), error);
+        result
+    }
+
+    fn cancelled(&self) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        result.append_cancelled(self.key.clone());
+        result
+    }
+}
+
+impl TransferItem for BucketUpload {
+    fn transfer_name(&self) -> &str {
+        &self.key
+    }
+
+    fn interrupted(&self, error: anyhow::Error) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        result.append_to_result(self.key.clone(), Err(error));
+        result
+    }
+
+    fn cancelled(&self) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        result.append_cancelled(self.key.clone());
+        result
+    }
+}
+
+impl TransferItem for BucketDeletion {
+    fn transfer_name(&self) -> &str {
+        self.key()
+    }
+
+    fn interrupted(&self, error: anyhow::Error) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        result.append_to_result(self.key().to_string(), Err(error));
+        result
+    }
+
+    fn cancelled(&self) -> DownloadResult {
+        let mut result = DownloadResult::default();
+        result.append_cancelled(self.key().to_string());
+        result
+    }
+}
+
+// an item shows up in the transfers only when it is taken to run, so that large
+// batches don't flood the panel. an item that didn't finish is reported on its
+// own, the results of the others are kept
+async fn run_transfers<I, F, Fut>(
+    items: Vec<I>,
+    transfers: &TransferReporter,
+    transfer: F,
+) -> DownloadResult
+where
+    I: TransferItem + Clone,
+    F: Fn(I, TransferHandle) -> Fut,
+    Fut: Future<Output = DownloadResult> + Send + 'static,
+{
+    let operations = (0..items.len()).map(|idx| {
+        let item = &items[idx];
+        transfer(item.clone(), transfers.queue(item.transfer_name()))
+    });
+    let results = transfers.run(operations).await;
+    let results = results
+        .into_iter()
+        .map(Some)
+        .chain(std::iter::repeat_with(|| None));
+    items
+        .iter()
+        .zip(results)
+        .fold(DownloadResult::default(), |acc, (item, result)| {
+            acc.merge_results(match result {
+                Some(result) => result.unwrap_or_else(|e| item.interrupted(e)),
+                None => item.cancelled(),
+            })
+        })
+}
+
 pub enum BucketItem {
     BucketFile(BucketFile),
     BucketFolder(BucketFolder),
@@ -133,14 +539,155 @@ impl From<TreeItem> for BucketItem {
 }
 
 impl Downloadable for BucketItem {
-    async fn download(
+    async fn list_files(
         &self,
         client: impl ProviderClient + Clone + 'static,
         source: String,
-    ) -> Result<DownloadResult> {
+        filters: &KeyFilters,
+    ) -> Result<Vec<(String, Result<String>)>> {
         match self {
-            BucketItem::BucketFile(file) => file.download(client, source.to_string()).await,
-            BucketItem::BucketFolder(folder) => folder.download(client, source.to_string()).await,
+            BucketItem::BucketFile(file) => file.list_files(client, source, filters).await,
+            BucketItem::BucketFolder(folder) => folder.list_files(client, source, filters).await,
+        }
+    }
+
+    async fn list_folder_objects(
+        &self,
+        client: impl ProviderClient + Clone + 'static,
+        source: String,
+    ) -> Result<Vec<FolderObject>> {
+        match self {
+            BucketItem::BucketFile(file) => file.list_folder_objects(client, source).await,
+            BucketItem::BucketFolder(folder) => folder.list_folder_objects(client, source).await,
+        }
+    }
+}
+
+// files streamed one after the other into a single local archive,
+// they are named after their path inside it
+pub struct BucketArchive {
+    name: String,
+    format: ArchiveFormat,
+    files: Vec<BucketFile>,
+}
+
+impl BucketArchive {
+    pub fn new(name: String, format: ArchiveFormat, files: Vec<(String, String)>) -> Self {
+        Self {
+            name,
+            format,
+            files: files
+                .into_iter()
+                .map(|(key, path)| BucketFile::new(key, path))
+                .collect(),
+        }
+    }
+
+    // local name to write the archive to when it already exists, None to skip it
+    async fn resolve_conflict(
+        &self,
+        handle: &TransferHandle,
+        conflict_policy: ConflictPolicy,
+    ) -> Result<Option<String>> {
+        // a new archive can't be compared with the existing one, which is kept
+        resolve(conflict_policy, &self.name, handle, |_| async { Ok(true) }).await
+    }
+
+    // files that can't be fetched are left out and reported, any failure once a file
+    // started streaming fails the whole archive
+    async fn write(
+        &self,
+        client: impl ProviderClient,
+        source: &str,
+        name: &str,
+        handle: &mut TransferHandle,
+    ) -> Result<DownloadResult> {
+        let mut result = DownloadResult::default();
+        let writer = ArchiveWriter::create(name, self.format)?;
+        handle.start(None);
+        let mut archived_bytes = 0;
+        for file in &self.files {
+            let remote_file = match client.open_file(source, &file.key).await {
+                Ok(remote_file) => remote_file,
+                Err(e) => {
+                    result.append_to_result(file.key.clone(), Err(e));
+                    continue;
+                }
+            };
+            let size = remote_file.file.size;
+            let added = writer
+                .add(file.name.clone(), remote_file, |bytes_done| {
+                    handle.progress(archived_bytes + bytes_done)
+                })
+                .await
+                .with_context(|| format!("File with key {} failed to download", file.key));
+            if let Err(e) = added {
+                writer.discard().await;
+                return Err(e);
+            }
+            archived_bytes += size;
+            result.append_to_result(file.key.clone(), Ok(true));
         }
+        writer.finish().await?;
+        Ok(result)
+    }
+
+    pub async fn download(
+        &self,
+        client: impl ProviderClient + Clone + 'static,
+        source: String,
+        transfers: TransferReporter,
+        conflict_policy: ConflictPolicy,
+    ) -> Result<DownloadResult> {
+        let mut result = DownloadResult::default();
+        let mut handle = transfers.queue(&self.name);
+        let token = handle.cancellation_token();
+        let resolved = tokio::select! {
+            biased;
+            _ = token.cancelled() => None,
+            resolved = self.resolve_conflict(&handle, conflict_policy) => Some(resolved),
+        };
+        let name = match resolved {
+            None => {
+                handle.cancelled();
+                result.append_cancelled(self.name.clone());
+                return Ok(result);
+            }
+            Some(Ok(None)) => {
+                handle.skipped();
+                result.append_skipped(self.name.clone());
+                return Ok(result);
+            }
+            Some(Ok(Some(name))) => name,
+            Some(Err(e)) => {
+                handle.fail(format!("{:#}", e));
+                result.append_to_result(self.name.clone(), Err(e));
+                return Ok(result);
+            }
+        };
+        let written = tokio::select! {
+            biased;
+            _ = token.cancelled() => None,
+            written = self.write(client, &source, &name, &mut handle) => Some(written),
+        };
+        match written {
+            None => {
+                PartialDownload::new(&name).discard();
+                handle.cancelled();
+                result.append_cancelled(self.name.clone());
+            }
+            Some(Ok(written)) => {
+                handle.complete();
+                if name != self.name {
+                    result.renamed.push((self.name.clone(), name));
+                }
+                result = result.merge_results(written);
+            }
+            Some(Err(e)) => {
+                handle.fail(format!("{:#}", e));
+                result.append_to_result(self.name.clone(), Err(e));
+            }
+        }
+        Ok(result)
     }
 }
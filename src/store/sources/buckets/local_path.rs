@@ -0,0 +1,65 @@
+use anyhow::{anyhow, Result};
+
+// characters that are not allowed in file names on at least one platform,
+// the backslash is a separator on windows and could be used to escape
+const INVALID_CHARACTERS: [char; 9] = ['\\', ':', '*', '?', '"', '<', '>', '|', '\0'];
+
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+fn escape_segment(segment: &str) -> String {
+    let escaped = segment
+        .chars()
+        .map(|c| {
+            if INVALID_CHARACTERS.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    let stem = escaped.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return format!("_{escaped}");
+    }
+    escaped
+}
+
+// maps the name built from an object key to a path relative to the download
+// directory: empty and `.` segments are dropped, so a leading `/` can't make it
+// absolute, and `..` is refused as it would write outside of the directory
+pub fn to_local_path(name: &str) -> Result<String> {
+    let mut segments = vec![];
+    for segment in name.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return Err(anyhow!("{name} points outside of the download folder")),
+            segment => segments.push(escape_segment(segment)),
+        }
+    }
+    if segments.is_empty() {
+        return Err(anyhow!("{name} is not a valid file name"));
+    }
+    Ok(segments.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_keys_inside_the_download_folder() {
+        assert_eq!(to_local_path("folder/file.txt").unwrap(), "folder/file.txt");
+        assert_eq!(to_local_path("/etc//./passwd").unwrap(), "etc/passwd");
+        assert_eq!(to_local_path("a/b:c?.txt").unwrap(), "a/b_c_.txt");
+        assert_eq!(to_local_path("..\\..\\x").unwrap(), ".._.._x");
+        assert_eq!(to_local_path("logs/nul.txt").unwrap(), "logs/_nul.txt");
+        assert!(to_local_path("folder/../../.bashrc").is_err());
+        assert!(to_local_path("/./").is_err());
+    }
+}
@@ -1,19 +1,83 @@
+use std::{collections::HashMap, sync::Arc};
+
 use anyhow::Result;
 
-use crate::providers::ProviderClient;
+use crate::{
+    providers::ProviderClient,
+    store::{
+        archives::ArchiveSettings, conflicts::ConflictPolicy, explorer::TreeItem,
+        filters::KeyFilters,
+    },
+};
 
 pub trait WithSources {
     fn set_source_with_idx(&mut self, idx: usize) -> &Option<String>;
     fn get_active_source(&self) -> &Option<String>;
     fn get_available_sources(&self) -> &Vec<String>;
+    fn get_sources_regions(&self) -> HashMap<String, String>;
     async fn update_available_sources(&mut self) -> Result<&Vec<String>>;
 }
 
+// choices made in the download popup, shared by all the files of a download
+#[derive(Debug, Clone, Default)]
+pub struct DownloadSettings {
+    // directory the items are downloaded into, the working directory when empty
+    pub destination: String,
+    pub conflict_policy: ConflictPolicy,
+    // the items are bundled in a single archive instead of being downloaded one by one
+    pub archive: Option<ArchiveSettings>,
+    // only the keys of the selected folders matching them are downloaded
+    pub filters: KeyFilters,
+}
+
+// object found under a selected folder, before any filter is applied
+#[derive(Debug, Clone)]
+pub struct FolderObject {
+    // key relative to the folder, the one filters are matched against
+    pub relative_key: String,
+    pub size: u64,
+}
+
+// content of the selected folders, listed once when the download popup opens
+// so the filters can be previewed while they are typed
+#[derive(Debug, Clone)]
+pub struct DownloadPreview {
+    // keys of the selected folders the listing belongs to
+    pub folders: Vec<String>,
+    pub objects: Result<Arc<Vec<FolderObject>>, String>,
+}
+
+impl DownloadPreview {
+    // keys of the folders among the selected items, only their content is listed
+    pub fn selected_folders(items: &[TreeItem]) -> Vec<String> {
+        items
+            .iter()
+            .filter_map(|item| match item {
+                TreeItem::Folder(folder, _) => Some(folder.name.clone()),
+                TreeItem::File(..) => None,
+            })
+            .collect()
+    }
+
+    // number of objects and bytes matching the filters, out of the objects listed
+    pub fn matching(&self, filters: &KeyFilters) -> Option<(usize, u64, usize)> {
+        let objects = self.objects.as_ref().ok()?;
+        let (count, bytes) = objects
+            .iter()
+            .filter(|object| filters.matches(&object.relative_key))
+            .fold((0, 0), |(count, bytes), object| {
+                (count + 1, bytes + object.size)
+            });
+        Some((count, bytes, objects.len()))
+    }
+}
+
 #[derive(Default, Debug)]
 pub enum DownloadResultStatus {
     Successful,
     Failed,
     Partial,
+    Cancelled,
     #[default]
     Pending,
 }
@@ -22,6 +86,24 @@ pub enum DownloadResultStatus {
 pub struct DownloadResult {
     pub status: DownloadResultStatus,
     pub results: Vec<(String, Result<bool>)>,
+    // keys of the items whose download was cancelled by the user
+    pub cancelled: Vec<String>,
+    // keys that can't be written to a local path, with the reason
+    pub rejected: Vec<(String, String)>,
+    // keys left alone because a local file already exists
+    pub skipped: Vec<String>,
+    // keys downloaded under another name to keep the existing local file
+    pub renamed: Vec<(String, String)>,
+    // keys that failed downloading with the local name they were mapped to
+    pub failed: Vec<(String, String)>,
+}
+
+// failed files of a download, offered to be downloaded again from the result alert
+#[derive(Debug, Clone, Default)]
+pub struct FailedDownloads {
+    pub source: String,
+    pub conflict_policy: ConflictPolicy,
+    pub files: Vec<(String, String)>,
 }
 
 impl DownloadResult {
@@ -31,6 +113,7 @@ impl DownloadResult {
                 self.status = DownloadResultStatus::Partial
             }
             DownloadResultStatus::Failed if !is_err => self.status = DownloadResultStatus::Partial,
+            DownloadResultStatus::Cancelled => self.status = DownloadResultStatus::Partial,
             DownloadResultStatus::Pending => {
                 self.status = if is_err {
                     DownloadResultStatus::Failed
@@ -48,9 +131,16 @@ impl DownloadResult {
             | (DownloadResultStatus::Failed, DownloadResultStatus::Successful)
             | (DownloadResultStatus::Failed, DownloadResultStatus::Partial)
             | (DownloadResultStatus::Partial, DownloadResultStatus::Successful)
-            | (DownloadResultStatus::Partial, DownloadResultStatus::Failed) => {
+            | (DownloadResultStatus::Partial, DownloadResultStatus::Failed)
+            | (DownloadResultStatus::Partial, DownloadResultStatus::Cancelled)
+            | (DownloadResultStatus::Successful, DownloadResultStatus::Cancelled)
+            | (DownloadResultStatus::Failed, DownloadResultStatus::Cancelled)
+            | (DownloadResultStatus::Cancelled, DownloadResultStatus::Successful)
+            | (DownloadResultStatus::Cancelled, DownloadResultStatus::Failed)
+            | (DownloadResultStatus::Cancelled, DownloadResultStatus::Partial) => {
                 self.status = DownloadResultStatus::Partial
             }
+            (DownloadResultStatus::Pending, other_status) => self.status = other_status,
             _ => {}
         }
     }
@@ -58,17 +148,53 @@ impl DownloadResult {
         self.update_status_on_append(result.is_err());
         self.results.push((file_key, result));
     }
+    pub fn append_cancelled(&mut self, file_key: String) {
+        self.status = match self.status {
+            DownloadResultStatus::Pending | DownloadResultStatus::Cancelled => {
+                DownloadResultStatus::Cancelled
+            }
+            _ => DownloadResultStatus::Partial,
+        };
+        self.cancelled.push(file_key);
+    }
+    pub fn append_failed(&mut self, file_key: String, local_name: String, error: anyhow::Error) {
+        self.failed.push((file_key.clone(), local_name));
+        self.append_to_result(file_key, Err(error));
+    }
+    pub fn append_rejected(&mut self, file_key: String, reason: String) {
+        self.update_status_on_append(true);
+        self.rejected.push((file_key, reason));
+    }
+    pub fn append_skipped(&mut self, file_key: String) {
+        self.update_status_on_append(false);
+        self.skipped.push(file_key);
+    }
     pub fn merge_results(mut self, other_result: DownloadResult) -> Self {
         self.update_status_on_merge(other_result.status);
         self.results.extend(other_result.results);
+        self.cancelled.extend(other_result.cancelled);
+        self.rejected.extend(other_result.rejected);
+        self.skipped.extend(other_result.skipped);
+        self.renamed.extend(other_result.renamed);
+        self.failed.extend(other_result.failed);
         self
     }
 }
 
 pub trait Downloadable {
-    async fn download(
+    // keys of all the files of the item matching the filters, with their path relative
+    // to the destination or the reason they can't be saved locally
+    async fn list_files(
+        &self,
+        client: impl ProviderClient + Clone + 'static,
+        source: String,
+        filters: &KeyFilters,
+    ) -> Result<Vec<(String, Result<String>)>>;
+
+    // objects under the item when it is a folder, files are never filtered
+    async fn list_folder_objects(
         &self,
         client: impl ProviderClient + Clone + 'static,
         source: String,
-    ) -> Result<DownloadResult>;
+    ) -> Result<Vec<FolderObject>>;
 }
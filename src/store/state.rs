@@ -4,34 +4,36 @@ pub mod ui_state;
 pub use app_state::{AppState, DashboardComponents};
 pub use state_events::StateEvents;
 
-use std::sync::Arc;
-
 use anyhow::Result;
-use tokio::sync::{
-    mpsc::{self, UnboundedReceiver, UnboundedSender},
-    Mutex,
-};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
-use crate::{action::Action, logger::LOGGER, providers::AwsClient};
+use crate::{action::Action, logger::LOGGER, providers::SharedClient};
 
 use super::{
     accounts::Accounts,
     action_manager::ActionManager,
+    destinations::Destinations,
     explorer::Explorer,
     notifications::Notifications,
+    preferences::Preferences,
     sources::{buckets::Buckets, Sources},
+    tasks::{BackgroundTasks, TaskResult},
+    transfers::TransferReporter,
 };
 
 pub struct State {
     pub app_state: AppState,
     pub tx: UnboundedSender<StateEvents>,
+    task_rx: UnboundedReceiver<TaskResult>,
 }
 
 impl State {
     pub async fn new(
-        client: Arc<Mutex<AwsClient>>,
+        client: SharedClient,
+        preferences: Preferences,
     ) -> Result<(Self, UnboundedReceiver<StateEvents>)> {
         let (tx, rx) = mpsc::unbounded_channel();
+        let (task_tx, task_rx) = mpsc::unbounded_channel();
         let accounts = Accounts::new(client.clone(), None).await?;
         let app_state = AppState {
             sources: Sources::Buckets(Buckets::new(client.clone())),
@@ -40,30 +42,47 @@ impl State {
             action_manager: ActionManager::default(),
             notifications: Notifications::default(),
             selected_component: DashboardComponents::default(),
+            tasks: BackgroundTasks::new(task_tx, tx.clone()),
+            transfers: TransferReporter::new(tx.clone(), preferences.max_concurrent_transfers),
+            conflict_policy: preferences.conflict_policy,
+            destinations: Destinations::load(),
+            download_preview: None,
+            sync_preview: None,
+            diff: None,
         };
-        Ok((Self { tx, app_state }, rx))
+        Ok((
+            Self {
+                tx,
+                app_state,
+                task_rx,
+            },
+            rx,
+        ))
     }
 
     pub async fn start(&mut self, mut ui_rx: UnboundedReceiver<Action>) -> Result<()> {
         // we need to send first state to unlock the ui
-        self.tx
-            .send(StateEvents::UpdateState(self.app_state.clone().into()))?;
-        // need to loop over ui_rx to react to user input
+        self.tx.send(StateEvents::UpdateState(Box::new(
+            self.app_state.clone().into(),
+        )))?;
+        // need to loop over ui_rx to react to user input, long running
+        // operations are spawned and their results come back through task_rx
         loop {
             tokio::select! {
                 Some(action) = ui_rx.recv() => {
                     self.app_state.action_manager.push(action.clone());
-                    let _ = LOGGER.info(&format!("{:?}", action));
+                    let _ = LOGGER.info(&format!("{:?}", action.redacted()));
                     match action {
                         Action::Quit => break Ok(()),
-                        Action::Tick => {},
-                        Action::Render => {},
-                        Action::Key(_) =>{},
                         _ => {
                             self.app_state = self.app_state.handle_state_action(action, self.tx.clone()).await?;
                         },
                     };
                 }
+                Some((task_id, task_event)) = self.task_rx.recv() => {
+                    self.tx.send(StateEvents::TaskFinished(task_id))?;
+                    self.app_state = self.app_state.handle_task_event(task_event, self.tx.clone())?;
+                }
             }
         }
     }
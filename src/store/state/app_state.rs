@@ -1,15 +1,28 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     action::Action,
     logger::LOGGER,
+    providers::is_known_region,
     store::{
         accounts::Accounts,
         action_manager::ActionManager,
+        conflicts::ConflictPolicy,
+        destinations::Destinations,
+        diff::DiffResult,
         explorer::Explorer,
         notifications::{types::NotificationType, Notifications},
-        sources::{buckets::entities::BucketItem, Sources, WithSources},
+        sources::{
+            buckets::entities::BucketItem,
+            traits::{DownloadPreview, DownloadResult, FailedDownloads},
+            Sources, WithSources,
+        },
+        sync::{SyncPreview, SyncResult},
+        tasks::{BackgroundTasks, TaskContext, TaskEvents},
+        transfers::TransferReporter,
     },
 };
 
@@ -21,6 +34,7 @@ pub enum DashboardComponents {
     #[default]
     Accounts,
     Explorer,
+    Transfers,
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +45,16 @@ pub struct AppState {
     pub action_manager: ActionManager,
     pub notifications: Notifications,
     pub selected_component: DashboardComponents,
+    pub tasks: BackgroundTasks,
+    pub transfers: TransferReporter,
+    pub conflict_policy: ConflictPolicy,
+    pub destinations: Destinations,
+    // listing of the folders selected in the download popup
+    pub download_preview: Option<DownloadPreview>,
+    // actions planned in the sync popup, waiting to be confirmed
+    pub sync_preview: Option<SyncPreview>,
+    // comparison shown over the dashboard until it is closed
+    pub diff: Option<DiffResult>,
 }
 
 impl DashboardComponents {
@@ -51,30 +75,54 @@ impl DashboardComponents {
             Action::DismissLastAlert => {
                 app_state.notifications.set_last_alert_as_shown();
             }
+            Action::CloseDiff => app_state.diff = None,
+            // failed files are retried from the result alert, whatever the component in focus
+            Action::RetryDownloads(failed) => {
+                let sources = app_state.sources.clone();
+                let transfers = app_state.transfers.clone();
+                let failed = failed.clone();
+                app_state.tasks.spawn(
+                    format!("Retrying {} items", failed.files.len()),
+                    async move {
+                        let source = failed.source.clone();
+                        let conflict_policy = failed.conflict_policy;
+                        TaskEvents::Downloaded {
+                            result: sources.retry(failed, transfers).await,
+                            source,
+                            conflict_policy,
+                        }
+                    },
+                );
+            }
+            // a conflict can be answered whatever the component in focus
+            Action::ResolveConflict(transfer_id, resolution) => {
+                app_state
+                    .transfers
+                    .resolve_conflict(*transfer_id, *resolution);
+            }
             unhandled_action => {
-                let _ = LOGGER.info(&format!("ignoring action {:#?}", unhandled_action));
+                let _ = LOGGER.info(&format!(
+                    "ignoring action {:#?}",
+                    unhandled_action.redacted()
+                ));
             }
         }
     }
     async fn handle_sources_action(&self, app_state: &mut AppState, action: &Action) {
         match action {
             Action::SetSource(source_idx) => {
-                let bucket = app_state.sources.set_source_with_idx(*source_idx);
+                let bucket = app_state.sources.set_source_with_idx(*source_idx).clone();
                 if let Some(bucket) = bucket {
-                    match app_state.explorer.create_file_tree(bucket).await {
-                        Ok(_) => {
-                            app_state.selected_component = DashboardComponents::Explorer;
-                            app_state.notifications.push_notification(
-                                format!("Source {bucket} has been selected"),
-                                false,
-                            );
-                        }
-                        Err(_) => {
-                            app_state
-                                .notifications
-                                .push_alert(format!("Failed to set source {bucket}"));
-                        }
-                    }
+                    let mut explorer = app_state.explorer.clone();
+                    app_state
+                        .tasks
+                        .spawn(format!("Loading source {bucket}"), async move {
+                            let result = explorer.create_file_tree(&bucket).await.map(|_| explorer);
+                            TaskEvents::SourceSet {
+                                source: bucket,
+                                result,
+                            }
+                        });
                 }
             }
             unhandled_action => self.default_actions(app_state, unhandled_action),
@@ -84,27 +132,44 @@ impl DashboardComponents {
     async fn handle_accounts_actions(&self, app_state: &mut AppState, action: &Action) {
         match action {
             Action::SetAccount(account_idx) => {
-                let account = app_state.accounts.set_account(*account_idx).await;
-                match app_state.sources.update_available_sources().await {
+                let account = app_state
+                    .accounts
+                    .set_account(*account_idx)
+                    .await
+                    .to_string();
+                let mut sources = app_state.sources.clone();
+                app_state
+                    .tasks
+                    .spawn(format!("Loading sources of {account}"), async move {
+                        let result = sources.update_available_sources().await.map(|_| ());
+                        TaskEvents::SourcesUpdated {
+                            account,
+                            result: result.map(|_| sources),
+                        }
+                    });
+            }
+            Action::ChangeRegion(new_region) => {
+                match app_state.accounts.change_region(new_region.clone()).await {
+                    Ok(_) if is_known_region(new_region) => {
+                        app_state
+                            .notifications
+                            .push_notification(format!("Region changed to {}", &new_region), false);
+                    }
                     Ok(_) => {
                         app_state.notifications.push_notification(
-                            format!("Account {account} has been selected"),
-                            false,
+                            format!(
+                                "Region changed to {new_region}, which is not a known aws region"
+                            ),
+                            true,
                         );
                     }
-                    Err(_) => {
+                    Err(e) => {
                         app_state
                             .notifications
-                            .push_alert(format!("Failed to set account {account}"));
+                            .push_alert(format!("Failed to change region: {e}"));
                     }
                 }
             }
-            Action::ChangeRegion(new_region) => {
-                app_state.accounts.change_region(new_region.clone()).await;
-                app_state
-                    .notifications
-                    .push_notification(format!("Region changed to {}", &new_region), false);
-            }
             Action::RefreshCredentials => match app_state.accounts.refresh_credentials().await {
                 Ok(_) => {
                     app_state
@@ -144,77 +209,145 @@ impl DashboardComponents {
     async fn handle_explorer_actions(&self, app_state: &mut AppState, action: &Action) {
         match action {
             Action::SetExplorerFolder(tree_item) => {
-                let new_selected_folder = app_state
-                    .explorer
-                    .update_file_tree(
-                        app_state.sources.get_active_source().as_ref().unwrap(),
-                        tree_item,
-                    )
-                    .await;
-                match new_selected_folder {
-                    Ok(folder) => {
-                        app_state.notifications.push_notification(
-                            format!("Folder {} has been selected", folder.name),
-                            false,
-                        );
-                    }
-                    Err(_) => {
-                        app_state
-                            .notifications
-                            .push_alert(format!("Failed to select item {:?}", tree_item));
-                    }
+                if let Some(bucket) = app_state.sources.get_active_source().clone() {
+                    let mut explorer = app_state.explorer.clone();
+                    let tree_item = tree_item.clone();
+                    app_state.tasks.spawn(
+                        format!("Loading folder {}", tree_item.name()),
+                        async move {
+                            let result = explorer
+                                .update_file_tree(&bucket, &tree_item)
+                                .await
+                                .map(|folder| (explorer, folder));
+                            TaskEvents::ExplorerFolderSet {
+                                source: bucket,
+                                tree_item,
+                                result,
+                            }
+                        },
+                    );
                 }
             }
-            Action::Download(items_to_download) => {
-                let items: Vec<BucketItem> = items_to_download
-                    .iter()
-                    .map(|item| item.clone().into())
-                    .collect();
-                let download_result = app_state.sources.download(items).await;
-
-                let _ = LOGGER.info(&format!("download result {download_result:#?}"));
-
-                match download_result {
-                    Ok(download_result) => {
-                        if download_result.results.iter().any(|(_, res)| res.is_err()) {
-                            let mut failed_items = vec![];
-                            for res in download_result.results {
-                                match res {
-                                    (file_key, Ok(_)) => {
-                                        app_state.notifications.push_notification(
-                                            format!(
-                                                "Successfully downloaded requested item {file_key}"
-                                            ),
-                                            false,
-                                        );
-                                    }
-                                    (file_key, Err(e)) => {
-                                        let _ = LOGGER
-                                            .info(&format!("error downloading item {file_key}"));
-                                        let _ = LOGGER.info(&format!("{:?}", e));
-                                        failed_items.push(file_key);
-                                    }
-                                }
+            Action::Download(items_to_download, settings) => {
+                if let Some(source) = app_state.sources.get_active_source().clone() {
+                    let items: Vec<BucketItem> = items_to_download
+                        .iter()
+                        .map(|item| item.clone().into())
+                        .collect();
+                    let sources = app_state.sources.clone();
+                    let transfers = app_state.transfers.clone();
+                    app_state
+                        .destinations
+                        .remember(&source, &settings.destination);
+                    let settings = settings.clone();
+                    app_state.tasks.spawn(
+                        format!("Downloading {} items", items.len()),
+                        async move {
+                            let conflict_policy = settings.conflict_policy;
+                            TaskEvents::Downloaded {
+                                result: sources.download(items, transfers, settings).await,
+                                source,
+                                conflict_policy,
                             }
-                            let mut alert_message = "These items failed downloading:".to_string();
-                            for item in &failed_items {
-                                alert_message.push_str(&format!("\n{item}"));
+                        },
+                    );
+                }
+            }
+            Action::PreviewDownload(items_to_list) => {
+                app_state.download_preview = None;
+                let folders = DownloadPreview::selected_folders(items_to_list);
+                if let (Some(source), false) = (
+                    app_state.sources.get_active_source().clone(),
+                    folders.is_empty(),
+                ) {
+                    let items: Vec<BucketItem> = items_to_list
+                        .iter()
+                        .map(|item| item.clone().into())
+                        .collect();
+                    let sources = app_state.sources.clone();
+                    app_state.tasks.spawn(
+                        format!("Listing {} folders", folders.len()),
+                        async move {
+                            let objects = sources
+                                .list_folder_objects(items)
+                                .await
+                                .map(Arc::new)
+                                .map_err(|e| format!("{e:#}"));
+                            TaskEvents::DownloadPreviewed {
+                                source,
+                                preview: DownloadPreview { folders, objects },
                             }
-                            app_state.notifications.push_alert(alert_message);
-                        } else {
-                            app_state.notifications.push_notification(
-                                "Successfully downloaded requested items".to_string(),
-                                false,
-                            );
-                        }
-                    }
-                    Err(_) => {
-                        app_state.notifications.push_alert(
-                            "An error occurred when downloading selected files".to_string(),
-                        );
-                    }
+                        },
+                    );
                 }
             }
+            Action::PlanSync(request) => {
+                app_state.sync_preview = None;
+                let context = app_state.task_context();
+                let sources = app_state.sources.clone();
+                let request = request.clone();
+                app_state.tasks.spawn(
+                    format!(
+                        "Comparing {} with s3://{}/{}",
+                        request.target.describe(),
+                        request.source,
+                        request.prefix
+                    ),
+                    async move {
+                        let plan = sources
+                            .plan_sync(request.clone())
+                            .await
+                            .map(Arc::new)
+                            .map_err(|e| format!("{e:#}"));
+                        TaskEvents::SyncPlanned {
+                            context,
+                            preview: SyncPreview { request, plan },
+                        }
+                    },
+                );
+            }
+            Action::CompareFolders(left, right) => {
+                app_state.diff = None;
+                let context = app_state.task_context();
+                let sources = app_state.sources.clone();
+                let (left, right) = (left.clone(), right.clone());
+                app_state.tasks.spawn(
+                    format!("Comparing {} with {}", left.describe(), right.describe()),
+                    async move {
+                        let diff = sources
+                            .diff(left.clone(), right.clone())
+                            .await
+                            .map(Arc::new)
+                            .map_err(|e| format!("{e:#}"));
+                        TaskEvents::Compared {
+                            context,
+                            diff: DiffResult { left, right, diff },
+                        }
+                    },
+                );
+            }
+            Action::RunSync(plan) => {
+                app_state.sync_preview = None;
+                let context = app_state.task_context();
+                let sources = app_state.sources.clone();
+                let transfers = app_state.transfers.clone();
+                let plan = plan.clone();
+                app_state.tasks.spawn(
+                    format!("Syncing {} items", plan.actions.len()),
+                    async move {
+                        let result = sources.sync(&plan, transfers).await.map(Box::new);
+                        TaskEvents::Synced { context, result }
+                    },
+                );
+            }
+            unhandled_action => self.default_actions(app_state, unhandled_action),
+        }
+    }
+
+    fn handle_transfers_actions(&self, app_state: &mut AppState, action: &Action) {
+        match action {
+            Action::CancelTransfer(transfer_id) => app_state.transfers.cancel(*transfer_id),
+            Action::CancelAllTransfers => app_state.transfers.cancel_all(),
             unhandled_action => self.default_actions(app_state, unhandled_action),
         }
     }
@@ -230,12 +363,249 @@ impl DashboardComponents {
             DashboardComponents::Explorer => {
                 self.handle_explorer_actions(&mut app_state, action).await
             }
+            DashboardComponents::Transfers => self.handle_transfers_actions(&mut app_state, action),
         }
         app_state
     }
 }
 
 impl AppState {
+    fn handle_download_result(
+        &mut self,
+        source: String,
+        conflict_policy: ConflictPolicy,
+        download_result: Result<DownloadResult>,
+    ) {
+        let _ = LOGGER.info(&format!("download result {download_result:#?}"));
+
+        match download_result {
+            Ok(download_result) => {
+                let cancelled_message = format!(
+                    "Download cancelled, {} items were not downloaded",
+                    download_result.cancelled.len()
+                );
+                if download_result.results.iter().any(|(_, res)| res.is_err())
+                    || !download_result.rejected.is_empty()
+                {
+                    let mut failed_items = vec![];
+                    for res in download_result.results {
+                        match res {
+                            (file_key, Ok(_)) => {
+                                self.notifications.push_notification(
+                                    format!("Successfully downloaded requested item {file_key}"),
+                                    false,
+                                );
+                            }
+                            (file_key, Err(e)) => {
+                                let _ = LOGGER.info(&format!("error downloading item {file_key}"));
+                                let _ = LOGGER.info(&format!("{:?}", e));
+                                failed_items.push(file_key);
+                            }
+                        }
+                    }
+                    let mut alert_message = String::new();
+                    if !failed_items.is_empty() {
+                        alert_message.push_str("These items failed downloading:");
+                        for item in &failed_items {
+                            alert_message.push_str(&format!("\n{item}"));
+                        }
+                    }
+                    if !download_result.rejected.is_empty() {
+                        if !alert_message.is_empty() {
+                            alert_message.push('\n');
+                        }
+                        alert_message.push_str("These keys can't be saved locally:");
+                        for (item, reason) in &download_result.rejected {
+                            alert_message.push_str(&format!("\n{item}: {reason}"));
+                        }
+                    }
+                    if !download_result.cancelled.is_empty() {
+                        self.notifications
+                            .push_notification(cancelled_message, false);
+                    }
+                    if download_result.failed.is_empty() {
+                        self.notifications.push_alert(alert_message);
+                    } else {
+                        self.notifications.push_alert_with_retry(
+                            alert_message,
+                            FailedDownloads {
+                                source,
+                                conflict_policy,
+                                files: download_result.failed,
+                            },
+                        );
+                    }
+                } else if !download_result.cancelled.is_empty() {
+                    self.notifications
+                        .push_notification(cancelled_message, false);
+                } else {
+                    let mut message = "Successfully downloaded requested items".to_string();
+                    if !download_result.skipped.is_empty() {
+                        message.push_str(&format!(
+                            ", {} skipped as they already exist",
+                            download_result.skipped.len()
+                        ));
+                    }
+                    if !download_result.renamed.is_empty() {
+                        message.push_str(&format!(
+                            ", {} renamed to keep the existing files",
+                            download_result.renamed.len()
+                        ));
+                    }
+                    self.notifications.push_notification(message, false);
+                }
+            }
+            Err(_) => {
+                self.notifications
+                    .push_alert("An error occurred when downloading selected files".to_string());
+            }
+        }
+    }
+
+    fn handle_sync_result(&mut self, sync_result: Result<Box<SyncResult>>) {
+        match sync_result {
+            Ok(sync_result) => {
+                // a single summary, only the failed keys are listed
+                let (summary, failed_items) = sync_result.summary();
+                let _ = LOGGER.info(&format!("sync result: {summary}"));
+                if failed_items.is_empty() {
+                    self.notifications
+                        .push_notification(format!("Sync finished: {summary}"), false);
+                } else {
+                    let mut alert_message =
+                        format!("Sync finished: {summary}\nThese items failed to sync:");
+                    for item in failed_items {
+                        alert_message.push_str(&format!("\n{item}"));
+                    }
+                    self.notifications.push_alert(alert_message);
+                }
+            }
+            Err(e) => {
+                let _ = LOGGER.info(&format!("sync failed: {e:#}"));
+                self.notifications
+                    .push_alert("An error occurred when syncing the folder".to_string());
+            }
+        }
+    }
+
+    // results of operations started before a switch of account or source are stale
+    fn apply_task_event(&mut self, task_event: TaskEvents) {
+        match task_event {
+            TaskEvents::SourcesUpdated { account, result } => {
+                if self.accounts.active_account.as_ref() != Some(&account) {
+                    return;
+                }
+                match result {
+                    Ok(sources) => {
+                        self.sources = sources;
+                        self.notifications.push_notification(
+                            format!("Account {account} has been selected"),
+                            false,
+                        );
+                    }
+                    Err(_) => {
+                        self.notifications
+                            .push_alert(format!("Failed to set account {account}"));
+                    }
+                }
+            }
+            TaskEvents::SourceSet { source, result } => {
+                if self.sources.get_active_source().as_ref() != Some(&source) {
+                    return;
+                }
+                match result {
+                    Ok(explorer) => {
+                        self.explorer = explorer;
+                        self.selected_component = DashboardComponents::Explorer;
+                        self.notifications
+                            .push_notification(format!("Source {source} has been selected"), false);
+                    }
+                    Err(_) => {
+                        self.notifications
+                            .push_alert(format!("Failed to set source {source}"));
+                    }
+                }
+            }
+            TaskEvents::ExplorerFolderSet {
+                source,
+                tree_item,
+                result,
+            } => {
+                if self.sources.get_active_source().as_ref() != Some(&source) {
+                    return;
+                }
+                match result {
+                    Ok((explorer, folder)) => {
+                        self.explorer = explorer;
+                        self.notifications.push_notification(
+                            format!("Folder {} has been selected", folder.name),
+                            false,
+                        );
+                    }
+                    Err(_) => {
+                        self.notifications
+                            .push_alert(format!("Failed to select item {:?}", tree_item));
+                    }
+                }
+            }
+            TaskEvents::Downloaded {
+                source,
+                conflict_policy,
+                result,
+            } => self.handle_download_result(source, conflict_policy, result),
+            TaskEvents::DownloadPreviewed { source, preview } => {
+                if self.sources.get_active_source().as_ref() != Some(&source) {
+                    return;
+                }
+                self.download_preview = Some(preview);
+            }
+            TaskEvents::SyncPlanned { context, preview } => {
+                if self.task_context() == context {
+                    self.sync_preview = Some(preview);
+                }
+            }
+            TaskEvents::Synced { context, result } => {
+                if self.task_context() == context {
+                    self.handle_sync_result(result);
+                }
+            }
+            TaskEvents::Compared { context, diff } => {
+                if self.task_context() == context {
+                    self.diff = Some(diff);
+                }
+            }
+        }
+    }
+
+    fn task_context(&self) -> TaskContext {
+        TaskContext {
+            account: self.accounts.active_account.clone(),
+            source: self.sources.get_active_source().clone(),
+        }
+    }
+
+    fn notify_ui(&self, tx: &UnboundedSender<StateEvents>) -> Result<()> {
+        let last_notification = self.notifications.get_last();
+        if let Some(NotificationType::Alert(alert)) = last_notification {
+            if !alert.has_been_shown() {
+                tx.send(StateEvents::Alert(alert.clone()))?;
+            }
+        }
+        tx.send(StateEvents::UpdateState(Box::new(self.clone().into())))?;
+        Ok(())
+    }
+
+    pub fn handle_task_event(
+        &mut self,
+        task_event: TaskEvents,
+        tx: UnboundedSender<StateEvents>,
+    ) -> Result<Self> {
+        let mut app_state = self.clone();
+        app_state.apply_task_event(task_event);
+        app_state.notify_ui(&tx)?;
+        Ok(app_state)
+    }
+
     pub async fn handle_state_action(
         &mut self,
         action: Action,
@@ -245,14 +615,7 @@ impl AppState {
             .selected_component
             .handle_action(self.clone(), &action)
             .await;
-
-        let last_notification = app_state.notifications.get_last();
-        if let Some(NotificationType::Alert(alert)) = last_notification {
-            if !alert.has_been_shown() {
-                tx.send(StateEvents::Alert(alert.clone()))?;
-            }
-        }
-        tx.send(StateEvents::UpdateState(app_state.clone().into()))?;
+        app_state.notify_ui(&tx)?;
         Ok(app_state)
     }
 }
@@ -1,8 +1,15 @@
-use crate::store::notifications::types::Notification;
+use crate::store::{
+    conflicts::FileConflict, notifications::types::Notification, tasks::Task,
+    transfers::TransferEvent,
+};
 
 use super::ui_state::UIState;
 
 pub enum StateEvents {
-    UpdateState(UIState),
+    UpdateState(Box<UIState>),
     Alert(Notification),
+    TaskStarted(Task),
+    TaskFinished(usize),
+    Transfer(TransferEvent),
+    Conflict(FileConflict),
 }
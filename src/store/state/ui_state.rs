@@ -1,11 +1,15 @@
+use std::collections::HashMap;
+
 use crate::{
     providers::AccountMap,
     store::{
         accounts::Accounts,
-        action_manager::ActionManager,
+        conflicts::ConflictPolicy,
+        diff::DiffResult,
         explorer::{Explorer, FileTree, Folder},
         notifications::Notifications,
-        sources::{Sources, WithSources},
+        sources::{traits::DownloadPreview, Sources, WithSources},
+        sync::SyncPreview,
     },
 };
 
@@ -13,6 +17,7 @@ use super::{AppState, DashboardComponents};
 
 pub struct UISources {
     pub available_sources: Vec<String>,
+    pub sources_regions: HashMap<String, String>,
     pub active_source: Option<String>,
 }
 
@@ -20,6 +25,7 @@ impl From<Sources> for UISources {
     fn from(value: Sources) -> Self {
         Self {
             available_sources: value.get_available_sources().clone(),
+            sources_regions: value.get_sources_regions(),
             active_source: value.get_active_source().clone(),
         }
     }
@@ -61,18 +67,34 @@ pub struct UIState {
     pub accounts: UIAccounts,
     pub notifications: Notifications,
     pub selected_component: DashboardComponents,
-    pub action_manager: ActionManager,
+    pub conflict_policy: ConflictPolicy,
+    // last destination used with the active source
+    pub download_destination: String,
+    pub download_preview: Option<DownloadPreview>,
+    pub sync_preview: Option<SyncPreview>,
+    pub diff: Option<DiffResult>,
 }
 
 impl From<AppState> for UIState {
     fn from(value: AppState) -> Self {
+        let download_destination = value
+            .sources
+            .get_active_source()
+            .as_ref()
+            .and_then(|source| value.destinations.get(source))
+            .cloned()
+            .unwrap_or_default();
         Self {
+            download_destination,
             sources: value.sources.into(),
             explorer: value.explorer.into(),
             accounts: value.accounts.into(),
             notifications: value.notifications,
             selected_component: value.selected_component,
-            action_manager: value.action_manager,
+            conflict_policy: value.conflict_policy,
+            download_preview: value.download_preview,
+            sync_preview: value.sync_preview,
+            diff: value.diff,
         }
     }
 }
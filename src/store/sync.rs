@@ -0,0 +1,582 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    providers::{matches_etag, RemoteFile},
+    store::{conflicts::seconds_since_epoch, sources::traits::DownloadResult},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncDirection {
+    // the target is copied to the selected folder
+    #[default]
+    ToFolder,
+    // the selected folder is copied to the target
+    FromFolder,
+}
+
+impl SyncDirection {
+    pub fn toggle(&self) -> Self {
+        match self {
+            SyncDirection::ToFolder => SyncDirection::FromFolder,
+            SyncDirection::FromFolder => SyncDirection::ToFolder,
+        }
+    }
+
+    pub fn label(&self, target: &SyncLocation) -> &'static str {
+        match (self, target) {
+            (SyncDirection::ToFolder, SyncLocation::Local(_)) => "local -> bucket",
+            (SyncDirection::FromFolder, SyncLocation::Local(_)) => "bucket -> local",
+            (SyncDirection::ToFolder, SyncLocation::Folder { .. }) => "other folder -> this one",
+            (SyncDirection::FromFolder, SyncLocation::Folder { .. }) => "this folder -> other one",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncLocation {
+    Local(String),
+    // prefix is the key of the folder, empty for the root of the bucket
+    Folder { source: String, prefix: String },
+}
+
+impl SyncLocation {
+    // `s3://bucket/prefix` is a folder of the account, anything else a local directory
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+        let Some(location) = value.strip_prefix("s3://") else {
+            return SyncLocation::Local(value.to_string());
+        };
+        let (source, prefix) = location.split_once('/').unwrap_or((location, ""));
+        let prefix = if prefix.is_empty() || prefix.ends_with('/') {
+            prefix.to_string()
+        } else {
+            format!("{prefix}/")
+        };
+        SyncLocation::Folder {
+            source: source.to_string(),
+            prefix,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            SyncLocation::Local(dir) => dir.clone(),
+            SyncLocation::Folder { source, prefix } => format!("s3://{source}/{prefix}"),
+        }
+    }
+
+    // how a file of the location is named in the plan
+    fn display_name(&self, name: &str) -> String {
+        match self {
+            SyncLocation::Local(dir) => local_path(dir, name),
+            SyncLocation::Folder { prefix, .. } => format!("{prefix}{name}"),
+        }
+    }
+}
+
+fn local_path(dir: &str, name: &str) -> String {
+    Path::new(dir).join(name).to_string_lossy().to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncRequest {
+    pub source: String,
+    // key of the folder, empty for the root of the bucket
+    pub prefix: String,
+    pub target: SyncLocation,
+    pub direction: SyncDirection,
+    // files missing from the copied side are deleted on the other one
+    pub delete: bool,
+}
+
+impl SyncRequest {
+    // location copied from, then location written to
+    pub fn sides(&self) -> (SyncLocation, SyncLocation) {
+        let folder = SyncLocation::Folder {
+            source: self.source.clone(),
+            prefix: self.prefix.clone(),
+        };
+        match self.direction {
+            SyncDirection::ToFolder => (self.target.clone(), folder),
+            SyncDirection::FromFolder => (folder, self.target.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncAction {
+    Upload {
+        path: String,
+        key: String,
+    },
+    Download {
+        key: String,
+        path: String,
+    },
+    // server side copy, the object never goes through the client
+    Copy {
+        source: String,
+        key: String,
+        to_source: String,
+        to_key: String,
+    },
+    DeleteLocal {
+        path: String,
+    },
+    DeleteRemote {
+        source: String,
+        key: String,
+    },
+}
+
+impl SyncAction {
+    pub fn describe(&self) -> String {
+        match self {
+            SyncAction::Upload { path, key } => format!("upload   {path} -> {key}"),
+            SyncAction::Download { key, path } => format!("download {key} -> {path}"),
+            SyncAction::Copy {
+                source,
+                key,
+                to_source,
+                to_key,
+            } => format!("copy     s3://{source}/{key} -> s3://{to_source}/{to_key}"),
+            SyncAction::DeleteLocal { path } => format!("delete   {path}"),
+            SyncAction::DeleteRemote { source, key } => format!("delete   s3://{source}/{key}"),
+        }
+    }
+}
+
+// what a sync would do, shown to the user before anything is changed
+#[derive(Clone, Default)]
+pub struct SyncPlan {
+    // bucket of the selected folder, the one uploads and downloads go through
+    pub source: String,
+    pub actions: Vec<SyncAction>,
+    pub unchanged: usize,
+    // keys that can't be mapped to a local path, with the reason
+    pub rejected: Vec<(String, String)>,
+}
+
+impl SyncPlan {
+    pub fn summary(&self) -> String {
+        let (mut transfers, mut deletions) = (0, 0);
+        for action in &self.actions {
+            match action {
+                SyncAction::Upload { .. }
+                | SyncAction::Download { .. }
+                | SyncAction::Copy { .. } => transfers += 1,
+                SyncAction::DeleteLocal { .. } | SyncAction::DeleteRemote { .. } => deletions += 1,
+            }
+        }
+        let mut summary = format!(
+            "{transfers} to copy, {deletions} to delete, {} unchanged",
+            self.unchanged
+        );
+        if !self.rejected.is_empty() {
+            summary.push_str(&format!(", {} ignored", self.rejected.len()));
+        }
+        summary
+    }
+}
+
+// plans are logged with every action that carries them, a large folder would
+// write one line per key
+impl fmt::Debug for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncPlan")
+            .field("source", &self.source)
+            .field("summary", &self.summary())
+            .finish()
+    }
+}
+
+// outcome of a sync, kept apart by kind of change so that it can be summed up
+#[derive(Debug, Default)]
+pub struct SyncResult {
+    // downloaded files
+    pub downloads: DownloadResult,
+    // uploaded files and copied objects
+    pub uploads: DownloadResult,
+    pub deletions: DownloadResult,
+    // deletions left out because a copy was cancelled or failed
+    pub skipped_deletions: usize,
+}
+
+impl SyncResult {
+    // counts of every kind of change, the failed keys are listed with their reason
+    pub fn summary(&self) -> (String, Vec<String>) {
+        let done = |result: &DownloadResult| {
+            result
+                .results
+                .iter()
+                .filter(|(_, result)| result.is_ok())
+                .count()
+        };
+        let failed = [&self.uploads, &self.downloads, &self.deletions]
+            .into_iter()
+            .flat_map(|result| &result.results)
+            .filter_map(|(key, result)| result.as_ref().err().map(|e| format!("{key}: {e:#}")))
+            .collect::<Vec<_>>();
+        let mut summary = format!(
+            "{} uploaded, {} downloaded, {} deleted, {} failed",
+            done(&self.uploads),
+            done(&self.downloads),
+            done(&self.deletions),
+            failed.len()
+        );
+        let cancelled = self.uploads.cancelled.len()
+            + self.downloads.cancelled.len()
+            + self.deletions.cancelled.len();
+        if cancelled > 0 {
+            summary.push_str(&format!(", {cancelled} cancelled"));
+        }
+        if self.skipped_deletions > 0 {
+            summary.push_str(&format!(
+                ", {} deletions skipped as not everything was copied",
+                self.skipped_deletions
+            ));
+        }
+        (summary, failed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncPreview {
+    pub request: SyncRequest,
+    pub plan: Result<Arc<SyncPlan>, String>,
+}
+
+// a file on either side of a sync
+#[derive(Debug, Clone)]
+pub struct SyncEntry {
+    // path relative to the directory or key relative to the folder
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    // only known for objects
+    pub etag: Option<String>,
+}
+
+impl SyncEntry {
+    pub fn remote(name: String, remote_file: RemoteFile) -> Self {
+        Self {
+            name,
+            size: remote_file.size,
+            modified: remote_file.last_modified,
+            etag: remote_file.etag,
+        }
+    }
+
+    // an object modified an hour ago, shared by the tests of the sync and the diff
+    #[cfg(test)]
+    pub fn object(name: &str, size: u64, etag: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            size,
+            modified: Some(SystemTime::now() - std::time::Duration::from_secs(3600)),
+            etag: Some(etag.to_string()),
+        }
+    }
+}
+
+// files under the directory with their path relative to it, separated by slashes.
+// leftovers of interrupted downloads are not part of the directory content
+pub fn list_local_files(dir: &Path) -> Result<Vec<SyncEntry>> {
+    let mut files = vec![];
+    if !dir.exists() {
+        return Ok(files);
+    }
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative_dir) = pending.pop() {
+        for entry in fs::read_dir(dir.join(&relative_dir))? {
+            let entry = entry?;
+            let relative_path = relative_dir.join(entry.file_name());
+            let metadata = fs::metadata(entry.path())?;
+            if metadata.is_dir() {
+                pending.push(relative_path);
+                continue;
+            }
+            let relative_path = relative_path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if relative_path.ends_with(".part") || relative_path.ends_with(".part.etag") {
+                continue;
+            }
+            files.push(SyncEntry {
+                name: relative_path,
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+                etag: None,
+            });
+        }
+    }
+    Ok(files)
+}
+
+// etags of single part uploads are the md5 of the content
+fn is_md5(etag: &str) -> bool {
+    !etag.contains('-')
+}
+
+// None when the content can't be compared without downloading it
+pub fn same_content(
+    (location, entry): (&SyncLocation, &SyncEntry),
+    (other_location, other): (&SyncLocation, &SyncEntry),
+) -> Result<Option<bool>> {
+    match (location, &entry.etag, other_location, &other.etag) {
+        (_, Some(etag), _, Some(other_etag)) => Ok(if etag == other_etag {
+            Some(true)
+        } else if is_md5(etag) && is_md5(other_etag) {
+            Some(false)
+        } else {
+            None
+        }),
+        (SyncLocation::Local(dir), None, _, Some(etag))
+        | (_, Some(etag), SyncLocation::Local(dir), None) => {
+            let local = if entry.etag.is_none() { entry } else { other };
+            matches_etag(Path::new(&local_path(dir, &local.name)), etag)
+        }
+        _ => Ok(None),
+    }
+}
+
+// the content is compared when the etags allow it, the dates otherwise: the copy
+// is only replaced when the original has been modified after it
+fn differs(
+    copied: (&SyncLocation, &SyncEntry),
+    other: (&SyncLocation, &SyncEntry),
+) -> Result<bool> {
+    if copied.1.size != other.1.size {
+        return Ok(true);
+    }
+    if let Some(same_content) = same_content(copied, other)? {
+        return Ok(!same_content);
+    }
+    let copied_modified = copied.1.modified.and_then(seconds_since_epoch);
+    let other_modified = other.1.modified.and_then(seconds_since_epoch);
+    Ok(match (copied_modified, other_modified) {
+        (Some(copied_modified), Some(other_modified)) => copied_modified > other_modified,
+        _ => true,
+    })
+}
+
+fn transfer(from: &SyncLocation, to: &SyncLocation, key: &str, entry: &SyncEntry) -> SyncAction {
+    match (from, to) {
+        (SyncLocation::Local(dir), SyncLocation::Folder { prefix, .. }) => SyncAction::Upload {
+            path: local_path(dir, &entry.name),
+            key: format!("{prefix}{}", entry.name),
+        },
+        (SyncLocation::Folder { prefix, .. }, SyncLocation::Local(dir)) => SyncAction::Download {
+            key: format!("{prefix}{}", entry.name),
+            path: local_path(dir, key),
+        },
+        (
+            SyncLocation::Folder { source, prefix },
+            SyncLocation::Folder {
+                source: to_source,
+                prefix: to_prefix,
+            },
+        ) => SyncAction::Copy {
+            source: source.clone(),
+            key: format!("{prefix}{}", entry.name),
+            to_source: to_source.clone(),
+            to_key: format!("{to_prefix}{}", entry.name),
+        },
+        (SyncLocation::Local(_), SyncLocation::Local(_)) => {
+            unreachable!("one side of a sync is always the selected folder")
+        }
+    }
+}
+
+fn delete(location: &SyncLocation, entry: &SyncEntry) -> SyncAction {
+    match location {
+        SyncLocation::Local(dir) => SyncAction::DeleteLocal {
+            path: local_path(dir, &entry.name),
+        },
+        SyncLocation::Folder { source, prefix } => SyncAction::DeleteRemote {
+            source: source.clone(),
+            key: format!("{prefix}{}", entry.name),
+        },
+    }
+}
+
+// a folder copied into itself would copy its own copies
+fn check_overlap(from: &SyncLocation, to: &SyncLocation) -> Result<()> {
+    if let (
+        SyncLocation::Folder { source, prefix },
+        SyncLocation::Folder {
+            source: to_source,
+            prefix: to_prefix,
+        },
+    ) = (from, to)
+    {
+        if source == to_source && (prefix.starts_with(to_prefix) || to_prefix.starts_with(prefix)) {
+            bail!("{} and {} overlap", from.describe(), to.describe());
+        }
+    }
+    Ok(())
+}
+
+// files keyed by the path they are matched with on the other side, objects that
+// can't be mapped to a local path are rejected
+pub fn files_by_path(
+    location: &SyncLocation,
+    other: &SyncLocation,
+    files: Vec<SyncEntry>,
+    to_local_path: impl Fn(&str) -> Result<String>,
+    rejected: &mut Vec<(String, String)>,
+) -> HashMap<String, SyncEntry> {
+    let mut files_by_path = HashMap::new();
+    for file in files {
+        let path = match (location, other) {
+            (SyncLocation::Folder { .. }, SyncLocation::Local(_)) => to_local_path(&file.name),
+            _ => Ok(file.name.clone()),
+        };
+        match path {
+            Ok(path) => {
+                files_by_path.insert(path, file);
+            }
+            Err(e) => rejected.push((location.display_name(&file.name), format!("{e:#}"))),
+        }
+    }
+    files_by_path
+}
+
+// files of both sides are matched by their path, objects are mapped with
+// to_local_path when the other side is a local directory
+pub fn plan(
+    request: &SyncRequest,
+    from_files: Vec<SyncEntry>,
+    to_files: Vec<SyncEntry>,
+    to_local_path: impl Fn(&str) -> Result<String>,
+) -> Result<SyncPlan> {
+    let (from, to) = request.sides();
+    check_overlap(&from, &to)?;
+    let mut plan = SyncPlan {
+        source: request.source.clone(),
+        ..SyncPlan::default()
+    };
+    let from_by_path = files_by_path(&from, &to, from_files, &to_local_path, &mut plan.rejected);
+    let to_by_path = files_by_path(&to, &from, to_files, &to_local_path, &mut plan.rejected);
+    for (path, file) in &from_by_path {
+        let up_to_date = match to_by_path.get(path) {
+            Some(other) => !differs((&from, file), (&to, other))?,
+            None => false,
+        };
+        if up_to_date {
+            plan.unchanged += 1;
+        } else {
+            plan.actions.push(transfer(&from, &to, path, file));
+        }
+    }
+    if request.delete {
+        let copied = from_by_path.keys().collect::<HashSet<_>>();
+        for (path, file) in &to_by_path {
+            if !copied.contains(path) {
+                plan.actions.push(delete(&to, file));
+            }
+        }
+    }
+    plan.actions.sort_by_key(SyncAction::describe);
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plans_only_what_differs() {
+        let dir = std::env::temp_dir().join(format!("s3li-sync-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("same.txt"), "hello world").unwrap();
+        fs::write(dir.join("changed.txt"), "hello there").unwrap();
+        fs::write(dir.join("nested/new.txt"), "new").unwrap();
+        fs::write(dir.join("nested/new.txt.part"), "leftover").unwrap();
+        let local_dir = dir.to_string_lossy().to_string();
+
+        let mut request = SyncRequest {
+            source: "bucket".to_string(),
+            prefix: "folder/".to_string(),
+            target: SyncLocation::parse(&local_dir),
+            direction: SyncDirection::ToFolder,
+            delete: true,
+        };
+        let local_files = list_local_files(&dir).unwrap();
+        assert_eq!(local_files.len(), 3);
+        // md5 of "hello world"
+        let md5 = "\"5eb63bbbe01eeed093cb22bb8f5acdc3\"";
+        let remote_files = vec![
+            SyncEntry::object("same.txt", 11, md5),
+            SyncEntry::object("changed.txt", 11, md5),
+            SyncEntry::object("gone.txt", 4, "\"abc-2\""),
+        ];
+        let upload = plan(&request, local_files.clone(), remote_files.clone(), |key| {
+            Ok(key.to_string())
+        })
+        .unwrap();
+        assert_eq!(upload.unchanged, 1);
+        assert_eq!(
+            upload.actions,
+            vec![
+                SyncAction::DeleteRemote {
+                    source: "bucket".to_string(),
+                    key: "folder/gone.txt".to_string()
+                },
+                SyncAction::Upload {
+                    path: local_path(&local_dir, "changed.txt"),
+                    key: "folder/changed.txt".to_string()
+                },
+                SyncAction::Upload {
+                    path: local_path(&local_dir, "nested/new.txt"),
+                    key: "folder/nested/new.txt".to_string()
+                },
+            ]
+        );
+
+        request.direction = SyncDirection::FromFolder;
+        request.delete = false;
+        let download = plan(&request, remote_files.clone(), local_files, |key| {
+            Ok(key.to_string())
+        })
+        .unwrap();
+        assert_eq!(download.unchanged, 1);
+        assert_eq!(download.actions.len(), 2);
+
+        // objects are compared by etag, a copy made later than its original is up to date
+        request.target = SyncLocation::parse("s3://other/backup");
+        let mut copies = remote_files.clone();
+        copies[1].etag = Some("\"d41d8cd98f00b204e9800998ecf8427e\"".to_string());
+        copies[2].modified = Some(SystemTime::now());
+        let copy = plan(&request, remote_files.clone(), copies, |key| {
+            Ok(key.to_string())
+        })
+        .unwrap();
+        assert_eq!(copy.unchanged, 2);
+        assert_eq!(
+            copy.actions,
+            vec![SyncAction::Copy {
+                source: "bucket".to_string(),
+                key: "folder/changed.txt".to_string(),
+                to_source: "other".to_string(),
+                to_key: "backup/changed.txt".to_string(),
+            }]
+        );
+
+        request.target = SyncLocation::parse("s3://bucket/folder/nested");
+        assert!(plan(&request, vec![], vec![], |key| Ok(key.to_string())).is_err());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,108 @@
+use std::future::Future;
+
+use anyhow::Result;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    conflicts::ConflictPolicy,
+    diff::DiffResult,
+    explorer::{Explorer, Folder, TreeItem},
+    sources::{
+        traits::{DownloadPreview, DownloadResult},
+        Sources,
+    },
+    state::StateEvents,
+    sync::{SyncPreview, SyncResult},
+};
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub id: usize,
+    pub description: String,
+}
+
+// account and source active when an operation was started, its result is dropped
+// once either of them changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskContext {
+    pub account: Option<String>,
+    pub source: Option<String>,
+}
+
+// results of the operations run in the background, they are applied to the
+// app state by the state loop once they are received
+pub enum TaskEvents {
+    SourcesUpdated {
+        account: String,
+        result: Result<Sources>,
+    },
+    SourceSet {
+        source: String,
+        result: Result<Explorer>,
+    },
+    ExplorerFolderSet {
+        source: String,
+        tree_item: TreeItem,
+        result: Result<(Explorer, Folder)>,
+    },
+    Downloaded {
+        source: String,
+        conflict_policy: ConflictPolicy,
+        result: Result<DownloadResult>,
+    },
+    DownloadPreviewed {
+        source: String,
+        preview: DownloadPreview,
+    },
+    SyncPlanned {
+        context: TaskContext,
+        preview: SyncPreview,
+    },
+    Synced {
+        context: TaskContext,
+        result: Result<Box<SyncResult>>,
+    },
+    Compared {
+        context: TaskContext,
+        diff: DiffResult,
+    },
+}
+
+pub type TaskResult = (usize, TaskEvents);
+
+#[derive(Debug, Clone)]
+pub struct BackgroundTasks {
+    next_id: usize,
+    task_tx: UnboundedSender<TaskResult>,
+    state_tx: UnboundedSender<StateEvents>,
+}
+
+impl BackgroundTasks {
+    pub fn new(
+        task_tx: UnboundedSender<TaskResult>,
+        state_tx: UnboundedSender<StateEvents>,
+    ) -> Self {
+        Self {
+            next_id: 0,
+            task_tx,
+            state_tx,
+        }
+    }
+
+    pub fn spawn<F>(&mut self, description: String, task: F) -> usize
+    where
+        F: Future<Output = TaskEvents> + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        let _ = self
+            .state_tx
+            .send(StateEvents::TaskStarted(Task { id, description }));
+        let task_tx = self.task_tx.clone();
+        tokio::spawn(async move {
+            let result = task.await;
+            let _ = task_tx.send((id, result));
+        });
+        id
+    }
+}
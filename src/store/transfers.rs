@@ -0,0 +1,579 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use futures::FutureExt;
+use tokio::{
+    sync::{mpsc::UnboundedSender, oneshot, Semaphore},
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    conflicts::{ConflictResolution, FileConflict},
+    state::StateEvents,
+};
+
+// progress is reported at most this often per transfer to avoid flooding the ui
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+// finished transfers kept in the list, the oldest ones are dropped first
+const MAX_FINISHED_TRANSFERS: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed(String),
+    Cancelled,
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub enum TransferEvent {
+    Queued { id: usize, name: String },
+    Started { id: usize, total_bytes: Option<u64> },
+    Progress { id: usize, bytes_done: u64 },
+    Completed { id: usize },
+    Failed { id: usize, error: String },
+    Cancelled { id: usize },
+    Skipped { id: usize },
+}
+
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub id: usize,
+    pub name: String,
+    pub status: TransferStatus,
+    pub bytes_done: u64,
+    pub total_bytes: Option<u64>,
+    started_at: Option<Instant>,
+    finished_at: Option<Instant>,
+}
+
+impl Transfer {
+    fn new(id: usize, name: String) -> Self {
+        Self {
+            id,
+            name,
+            status: TransferStatus::Queued,
+            bytes_done: 0,
+            total_bytes: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    fn elapsed(&self, now: Instant) -> Option<Duration> {
+        let started_at = self.started_at?;
+        Some(self.finished_at.unwrap_or(now).duration_since(started_at))
+    }
+
+    // average bytes per second since the transfer started
+    pub fn throughput(&self, now: Instant) -> Option<f64> {
+        let elapsed = self.elapsed(now)?.as_secs_f64();
+        if elapsed <= 0.0 || self.bytes_done == 0 {
+            return None;
+        }
+        Some(self.bytes_done as f64 / elapsed)
+    }
+
+    pub fn eta(&self, now: Instant) -> Option<Duration> {
+        if self.status != TransferStatus::Running {
+            return None;
+        }
+        let remaining = self.total_bytes?.saturating_sub(self.bytes_done);
+        let throughput = self.throughput(now)?;
+        Some(Duration::from_secs_f64(remaining as f64 / throughput))
+    }
+
+    fn is_finished(&self) -> bool {
+        !matches!(
+            self.status,
+            TransferStatus::Queued | TransferStatus::Running
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Transfers {
+    transfers: Vec<Transfer>,
+}
+
+impl Transfers {
+    pub fn apply(&mut self, event: TransferEvent) {
+        self.apply_at(event, Instant::now());
+    }
+
+    fn apply_at(&mut self, event: TransferEvent, now: Instant) {
+        match event {
+            TransferEvent::Queued { id, name } => {
+                self.transfers.push(Transfer::new(id, name));
+                self.prune_finished();
+            }
+            TransferEvent::Started { id, total_bytes } => {
+                if let Some(transfer) = self.get_mut(id) {
+                    transfer.status = TransferStatus::Running;
+                    transfer.total_bytes = total_bytes;
+                    transfer.started_at = Some(now);
+                }
+            }
+            TransferEvent::Progress { id, bytes_done } => {
+                if let Some(transfer) = self.get_mut(id) {
+                    transfer.bytes_done = bytes_done;
+                }
+            }
+            TransferEvent::Completed { id } => {
+                if let Some(transfer) = self.get_mut(id) {
+                    transfer.status = TransferStatus::Completed;
+                    transfer.finished_at = Some(now);
+                    if let Some(total_bytes) = transfer.total_bytes {
+                        transfer.bytes_done = total_bytes;
+                    }
+                }
+            }
+            TransferEvent::Failed { id, error } => {
+                if let Some(transfer) = self.get_mut(id) {
+                    transfer.status = TransferStatus::Failed(error);
+                    transfer.finished_at = Some(now);
+                }
+            }
+            TransferEvent::Cancelled { id } => {
+                if let Some(transfer) = self.get_mut(id) {
+                    transfer.status = TransferStatus::Cancelled;
+                    transfer.finished_at = Some(now);
+                }
+            }
+            TransferEvent::Skipped { id } => {
+                if let Some(transfer) = self.get_mut(id) {
+                    transfer.status = TransferStatus::Skipped;
+                    transfer.finished_at = Some(now);
+                }
+            }
+        }
+    }
+
+    // long sessions and large folders would otherwise grow the list forever
+    fn prune_finished(&mut self) {
+        let finished = self
+            .transfers
+            .iter()
+            .filter(|transfer| transfer.is_finished())
+            .count();
+        let mut to_remove = finished.saturating_sub(MAX_FINISHED_TRANSFERS);
+        self.transfers.retain(|transfer| {
+            if to_remove > 0 && transfer.is_finished() {
+                to_remove -= 1;
+                return false;
+            }
+            true
+        });
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut Transfer> {
+        self.transfers.iter_mut().find(|transfer| transfer.id == id)
+    }
+
+    pub fn get_transfers(&self) -> &Vec<Transfer> {
+        &self.transfers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+}
+
+type CancellationTokens = Arc<Mutex<HashMap<usize, CancellationToken>>>;
+type PendingConflicts = Arc<Mutex<HashMap<usize, oneshot::Sender<ConflictResolution>>>>;
+
+// handed down to the download path so that every file can report its progress,
+// it also keeps the cancellation tokens of the transfers that are not finished yet
+// and bounds how many transfers run at the same time across all the operations.
+// transfers waiting for the user to resolve a conflict keep the sender of the answer
+#[derive(Debug, Clone)]
+pub struct TransferReporter {
+    next_id: Arc<AtomicUsize>,
+    tokens: CancellationTokens,
+    conflicts: PendingConflicts,
+    permits: Arc<Semaphore>,
+    // bumped by cancel_all so that runs stop taking new jobs
+    cancelled_runs: Arc<AtomicUsize>,
+    state_tx: UnboundedSender<StateEvents>,
+}
+
+impl TransferReporter {
+    pub fn new(state_tx: UnboundedSender<StateEvents>, max_concurrency: usize) -> Self {
+        Self {
+            next_id: Arc::new(AtomicUsize::new(0)),
+            tokens: Arc::new(Mutex::new(HashMap::new())),
+            conflicts: Arc::new(Mutex::new(HashMap::new())),
+            permits: Arc::new(Semaphore::new(max_concurrency.max(1))),
+            cancelled_runs: Arc::new(AtomicUsize::new(0)),
+            state_tx,
+        }
+    }
+
+    // a job is taken from the iterator only once a permit is free, so there are never
+    // more tasks than permits however many jobs there are. the permits are shared by
+    // every run so concurrent operations don't add up. results are returned in the
+    // order of the jobs, a job that panicked is reported as an error. the jobs left
+    // when every transfer is cancelled are not even taken, only the started jobs
+    // have a result
+    pub async fn run<F, T>(&self, jobs: impl IntoIterator<Item = F>) -> Vec<Result<T>>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let cancelled_runs = self.cancelled_runs.load(Ordering::Relaxed);
+        let mut jobs = jobs.into_iter().enumerate();
+        let mut has_jobs = true;
+        let mut running = JoinSet::<(usize, std::thread::Result<T>)>::new();
+        let mut results = vec![];
+        loop {
+            tokio::select! {
+                biased;
+                Some(joined) = running.join_next() => {
+                    if let Ok((idx, result)) = joined {
+                        results[idx] =
+                            result.map_err(|_| anyhow!("the transfer stopped unexpectedly"));
+                    }
+                }
+                permit = self.permits.clone().acquire_owned(), if has_jobs => {
+                    let next = permit.ok().and_then(|permit| {
+                        if self.cancelled_runs.load(Ordering::Relaxed) != cancelled_runs {
+                            return None;
+                        }
+                        jobs.next().map(|job| (permit, job))
+                    });
+                    match next {
+                        Some((permit, (idx, job))) => {
+                            results.push(Err(anyhow!("the transfer was aborted")));
+                            running.spawn(async move {
+                                let _permit = permit;
+                                (idx, AssertUnwindSafe(job).catch_unwind().await)
+                            });
+                        }
+                        None => has_jobs = false,
+                    }
+                }
+                else => break,
+            }
+        }
+        results
+    }
+
+    pub fn queue(&self, name: &str) -> TransferHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = CancellationToken::new();
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.insert(id, token.clone());
+        }
+        let handle = TransferHandle {
+            id,
+            token,
+            finished: AtomicBool::new(false),
+            tokens: self.tokens.clone(),
+            conflicts: self.conflicts.clone(),
+            state_tx: self.state_tx.clone(),
+            last_report: None,
+        };
+        handle.send(TransferEvent::Queued {
+            id,
+            name: name.to_string(),
+        });
+        handle
+    }
+
+    pub fn cancel(&self, id: usize) {
+        if let Some(token) = self
+            .tokens
+            .lock()
+            .ok()
+            .and_then(|tokens| tokens.get(&id).cloned())
+        {
+            token.cancel();
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        self.cancelled_runs.fetch_add(1, Ordering::Relaxed);
+        if let Ok(tokens) = self.tokens.lock() {
+            tokens.values().for_each(|token| token.cancel());
+        }
+    }
+
+    pub fn resolve_conflict(&self, id: usize, resolution: ConflictResolution) {
+        let sender = self
+            .conflicts
+            .lock()
+            .ok()
+            .and_then(|mut conflicts| conflicts.remove(&id));
+        if let Some(sender) = sender {
+            let _ = sender.send(resolution);
+        }
+    }
+}
+
+pub struct TransferHandle {
+    id: usize,
+    token: CancellationToken,
+    finished: AtomicBool,
+    tokens: CancellationTokens,
+    conflicts: PendingConflicts,
+    state_tx: UnboundedSender<StateEvents>,
+    last_report: Option<Instant>,
+}
+
+impl TransferHandle {
+    fn send(&self, event: TransferEvent) {
+        let _ = self.state_tx.send(StateEvents::Transfer(event));
+    }
+
+    fn finish(&self, event: TransferEvent) {
+        self.finished.store(true, Ordering::Relaxed);
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.remove(&self.id);
+        }
+        if let Ok(mut conflicts) = self.conflicts.lock() {
+            conflicts.remove(&self.id);
+        }
+        self.send(event);
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn start(&mut self, total_bytes: Option<u64>) {
+        self.last_report = Some(Instant::now());
+        self.send(TransferEvent::Started {
+            id: self.id,
+            total_bytes,
+        });
+    }
+
+    pub fn progress(&mut self, bytes_done: u64) {
+        let now = Instant::now();
+        if self
+            .last_report
+            .is_some_and(|last_report| now.duration_since(last_report) < PROGRESS_INTERVAL)
+        {
+            return;
+        }
+        self.last_report = Some(now);
+        self.send(TransferEvent::Progress {
+            id: self.id,
+            bytes_done,
+        });
+    }
+
+    pub fn complete(&self) {
+        self.finish(TransferEvent::Completed { id: self.id });
+    }
+
+    pub fn fail(&self, error: String) {
+        self.finish(TransferEvent::Failed { id: self.id, error });
+    }
+
+    pub fn cancelled(&self) {
+        self.finish(TransferEvent::Cancelled { id: self.id });
+    }
+
+    pub fn skipped(&self) {
+        self.finish(TransferEvent::Skipped { id: self.id });
+    }
+
+    // the question is shown in the ui, the answer comes back through resolve_conflict
+    pub async fn ask_conflict(&self, name: &str) -> Option<ConflictResolution> {
+        let (tx, rx) = oneshot::channel();
+        if let Ok(mut conflicts) = self.conflicts.lock() {
+            conflicts.insert(self.id, tx);
+        }
+        let _ = self.state_tx.send(StateEvents::Conflict(FileConflict {
+            transfer_id: self.id,
+            name: name.to_string(),
+        }));
+        rx.await.ok()
+    }
+}
+
+// a handle dropped before the end of its transfer, e.g. by a panic, would leave
+// the transfer running forever in the panel
+impl Drop for TransferHandle {
+    fn drop(&mut self) {
+        if !self.finished.load(Ordering::Relaxed) {
+            self.fail("the transfer stopped unexpectedly".to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transfer_reports_throughput_and_eta() {
+        let mut transfers = Transfers::default();
+        let start = Instant::now();
+        transfers.apply_at(
+            TransferEvent::Queued {
+                id: 0,
+                name: "file".to_string(),
+            },
+            start,
+        );
+        transfers.apply_at(
+            TransferEvent::Started {
+                id: 0,
+                total_bytes: Some(300),
+            },
+            start,
+        );
+        transfers.apply_at(
+            TransferEvent::Progress {
+                id: 0,
+                bytes_done: 100,
+            },
+            start,
+        );
+        let transfer = &transfers.get_transfers()[0];
+        let now = start + Duration::from_secs(2);
+        assert_eq!(transfer.status, TransferStatus::Running);
+        assert_eq!(transfer.throughput(now), Some(50.0));
+        assert_eq!(transfer.eta(now), Some(Duration::from_secs(4)));
+
+        transfers.apply_at(TransferEvent::Completed { id: 0 }, now);
+        let transfer = &transfers.get_transfers()[0];
+        assert_eq!(transfer.status, TransferStatus::Completed);
+        assert_eq!(transfer.bytes_done, 300);
+        assert_eq!(transfer.eta(now + Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn drops_the_oldest_finished_transfers() {
+        let mut transfers = Transfers::default();
+        let now = Instant::now();
+        for id in 0..MAX_FINISHED_TRANSFERS + 2 {
+            let name = id.to_string();
+            transfers.apply_at(TransferEvent::Queued { id, name }, now);
+            if id != 0 {
+                transfers.apply_at(TransferEvent::Completed { id }, now);
+            }
+        }
+        transfers.apply_at(
+            TransferEvent::Queued {
+                id: MAX_FINISHED_TRANSFERS + 2,
+                name: "last".to_string(),
+            },
+            now,
+        );
+        let ids = transfers
+            .get_transfers()
+            .iter()
+            .map(|transfer| transfer.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), MAX_FINISHED_TRANSFERS + 2);
+        // the queued transfer is kept, the first completed one is dropped
+        assert_eq!(&ids[..2], &[0, 2]);
+    }
+
+    #[test]
+    fn cancels_only_unfinished_transfers() {
+        let (state_tx, _state_rx) = tokio::sync::mpsc::unbounded_channel();
+        let reporter = TransferReporter::new(state_tx, 1);
+        let first = reporter.queue("first");
+        let second = reporter.queue("second");
+        let third = reporter.queue("third");
+        third.complete();
+
+        reporter.cancel(first.id);
+        assert!(first.cancellation_token().is_cancelled());
+        assert!(!second.cancellation_token().is_cancelled());
+
+        reporter.cancel_all();
+        assert!(second.cancellation_token().is_cancelled());
+        assert!(!third.cancellation_token().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn answers_the_conflict_of_the_asking_transfer() {
+        let (state_tx, mut state_rx) = tokio::sync::mpsc::unbounded_channel();
+        let reporter = TransferReporter::new(state_tx, 1);
+        let handle = reporter.queue("file");
+        let answer = tokio::spawn(async move { handle.ask_conflict("file").await });
+
+        let transfer_id = loop {
+            if let Some(StateEvents::Conflict(conflict)) = state_rx.recv().await {
+                break conflict.transfer_id;
+            }
+        };
+        reporter.resolve_conflict(transfer_id, ConflictResolution::Rename);
+        assert_eq!(answer.await.unwrap(), Some(ConflictResolution::Rename));
+    }
+
+    #[tokio::test]
+    async fn runs_at_most_max_concurrency_jobs_across_runs() {
+        let (state_tx, _state_rx) = tokio::sync::mpsc::unbounded_channel();
+        let reporter = TransferReporter::new(state_tx, 3);
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let jobs = || {
+            (0..10).map(|idx| {
+                let running = running.clone();
+                let max_running = max_running.clone();
+                async move {
+                    if idx == 7 {
+                        panic!("job {idx} panicked");
+                    }
+                    let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_running.fetch_max(now_running, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    idx
+                }
+            })
+        };
+
+        let (first, second) = tokio::join!(reporter.run(jobs()), reporter.run(jobs()));
+        for results in [first, second] {
+            assert_eq!(results.len(), 10);
+            assert_eq!(results[3].as_ref().ok(), Some(&3));
+            assert!(results[7].is_err());
+        }
+        assert_eq!(max_running.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn takes_jobs_only_when_a_permit_is_free() {
+        let (state_tx, _state_rx) = tokio::sync::mpsc::unbounded_channel();
+        let reporter = TransferReporter::new(state_tx, 2);
+        let taken = Arc::new(AtomicUsize::new(0));
+        let jobs = (0..1000).map(|idx| {
+            taken.fetch_add(1, Ordering::SeqCst);
+            let taken = taken.clone();
+            let reporter = reporter.clone();
+            async move {
+                // every job started so far holds or is about to release a permit
+                assert!(taken.load(Ordering::SeqCst) <= idx + 2);
+                if idx == 3 {
+                    reporter.cancel_all();
+                }
+                idx
+            }
+        });
+
+        let results = reporter.run(jobs).await;
+        // a job panics when more jobs were taken than there are permits
+        assert!(results.len() >= 4 && results.len() < 10);
+        assert!(results.iter().all(|result| result.is_ok()));
+        assert_eq!(taken.load(Ordering::SeqCst), results.len());
+    }
+}
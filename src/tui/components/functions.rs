@@ -1,3 +1,19 @@
+use std::{
+    fs,
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use arboard::Clipboard;
+
+use crate::{providers::REDACTED_VALUE, store::destinations::expand_home};
+
+// the clipboard is kept alive for the whole session, on x11 the copied
+// value is lost as soon as the owner is dropped
+static CLIPBOARD: LazyLock<Mutex<Option<Clipboard>>> =
+    LazyLock::new(|| Mutex::new(Clipboard::new().ok()));
+
 pub fn add_white_space_till_width_if_needed(string: &str, width: usize) -> String {
     let mut line_item_label = format!("{: <25}", string);
     let line_item_label = if line_item_label.chars().count() < width {
@@ -11,3 +27,124 @@ pub fn add_white_space_till_width_if_needed(string: &str, width: usize) -> Strin
     };
     line_item_label
 }
+
+// the same mask as in the logs, its fixed width doesn't show the length of the secret
+pub fn mask_value(value: &str) -> String {
+    if value.is_empty() {
+        String::new()
+    } else {
+        REDACTED_VALUE.to_string()
+    }
+}
+
+pub fn copy_to_clipboard(value: &str) -> Result<()> {
+    let mut clipboard = CLIPBOARD
+        .lock()
+        .map_err(|_| anyhow!("clipboard is poisoned"))?;
+    match clipboard.as_mut() {
+        Some(clipboard) => Ok(clipboard.set_text(value)?),
+        None => Err(anyhow!("clipboard is not available")),
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds {
+        0..=59 => format!("{seconds}s"),
+        60..=3599 => format!("{}m{:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h{:02}m", seconds / 3600, (seconds % 3600) / 60),
+    }
+}
+
+// completes the last segment of a directory path as far as all the matching
+// directories agree, a single match is completed with a trailing slash
+pub fn complete_directory(path: &str) -> Option<String> {
+    if path == "~" {
+        return Some("~/".to_string());
+    }
+    let (parent, prefix) = match path.rfind('/') {
+        Some(idx) => path.split_at(idx + 1),
+        None => ("", path),
+    };
+    let dir = if parent.is_empty() {
+        expand_home(".")
+    } else {
+        expand_home(parent)
+    };
+    let mut matches = fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        // hidden directories only when asked for
+        .filter(|name| {
+            name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.'))
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+    let first = matches.first()?;
+    let common = matches.iter().fold(first.clone(), |common, name| {
+        common
+            .chars()
+            .zip(name.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect()
+    });
+    if matches.len() == 1 {
+        Some(format!("{parent}{common}/"))
+    } else {
+        Some(format!("{parent}{common}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completes_directories_only() {
+        let dir = std::env::temp_dir().join(format!("s3li-complete-{}", std::process::id()));
+        fs::create_dir_all(dir.join("alpha")).unwrap();
+        fs::create_dir_all(dir.join("alpine")).unwrap();
+        fs::write(dir.join("alps.txt"), "").unwrap();
+        let dir_name = dir.to_string_lossy();
+
+        assert_eq!(
+            complete_directory(&format!("{dir_name}/al")),
+            Some(format!("{dir_name}/alp"))
+        );
+        assert_eq!(
+            complete_directory(&format!("{dir_name}/alph")),
+            Some(format!("{dir_name}/alpha/"))
+        );
+        assert_eq!(complete_directory(&format!("{dir_name}/alps")), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn formats_bytes_and_durations() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h02m");
+    }
+}
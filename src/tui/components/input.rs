@@ -1,63 +1,207 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Position, Rect, Size},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
-    widgets::{Block, BorderType, Borders, Paragraph, Widget, Wrap},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Paragraph, Widget},
 };
 
+use super::functions::mask_value;
+
 trait WithCursor {
     fn get_inner_area(&self, section: &Rect) -> u16 {
-        section.width - 2
+        section.width.saturating_sub(2).max(1)
     }
     fn get_x(&self, section: &Rect) -> u16;
     fn get_y(&self, section: &Rect) -> u16;
     fn is_visible(&self) -> bool;
-    fn value_length(&self) -> u16;
     fn with_cursor(&self, section: Rect, buf: &mut ratatui::prelude::Buffer) {
-        if self.is_visible() {
-            const WIDTH: u16 = 1;
-            let last_position = Position {
-                x: self.get_x(&section),
-                y: self.get_y(&section),
-            };
-            let size = Size::new(WIDTH, 1);
-            let area = Rect::from((last_position, size));
-            fill(
-                area,
-                buf,
-                "█",
+        let (x, y) = (self.get_x(&section), self.get_y(&section));
+        if !self.is_visible() || x >= buf.area.right() || y >= buf.area.bottom() {
+            return;
+        }
+        let cell = buf.get_mut(x, y);
+        if cell.symbol().trim().is_empty() {
+            cell.set_symbol("█").set_style(
                 Style::default()
                     .fg(Color::White)
                     .add_modifier(Modifier::RAPID_BLINK),
             );
+        } else {
+            // cursor is in the middle of the value, keep the char visible
+            cell.set_style(Style::default().add_modifier(Modifier::REVERSED));
         }
     }
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InputState {
+    value: String,
+    // cursor position counted in chars, not bytes
+    cursor: usize,
+}
+
+impl InputState {
+    pub fn new(value: String) -> Self {
+        let cursor = value.chars().count();
+        Self { value, cursor }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn handle_key_event(&mut self, key: KeyEvent) {
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('w'), KeyModifiers::CONTROL) => self.delete_word(),
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => self.clear(),
+            (_, KeyModifiers::CONTROL) => {}
+            (KeyCode::Char(value), _) => self.insert_char(value),
+            (KeyCode::Backspace, _) => self.delete_char(),
+            (KeyCode::Delete, _) => self.delete_char_forward(),
+            (KeyCode::Left, _) => self.move_left(),
+            (KeyCode::Right, _) => self.move_right(),
+            (KeyCode::Home, _) => self.move_home(),
+            (KeyCode::End, _) => self.move_end(),
+            _ => {}
+        }
+    }
+
+    fn byte_idx(&self, char_idx: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(char_idx)
+            .map(|(idx, _)| idx)
+            .unwrap_or(self.value.len())
+    }
+
+    pub fn insert_char(&mut self, value: char) {
+        let idx = self.byte_idx(self.cursor);
+        self.value.insert(idx, value);
+        self.cursor += 1;
+    }
+
+    pub fn insert_str(&mut self, value: &str) {
+        let idx = self.byte_idx(self.cursor);
+        self.value.insert_str(idx, value);
+        self.cursor += value.chars().count();
+    }
+
+    pub fn delete_char(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let idx = self.byte_idx(self.cursor);
+            self.value.remove(idx);
+        }
+    }
+
+    pub fn delete_char_forward(&mut self) {
+        if self.cursor < self.value.chars().count() {
+            let idx = self.byte_idx(self.cursor);
+            self.value.remove(idx);
+        }
+    }
+
+    pub fn delete_word(&mut self) {
+        let before_cursor = self.value.chars().take(self.cursor).collect::<Vec<char>>();
+        let trailing_spaces = before_cursor
+            .iter()
+            .rev()
+            .take_while(|val| val.is_whitespace())
+            .count();
+        let word = before_cursor
+            .iter()
+            .rev()
+            .skip(trailing_spaces)
+            .take_while(|val| !val.is_whitespace())
+            .count();
+        let start = self.byte_idx(self.cursor - trailing_spaces - word);
+        let end = self.byte_idx(self.cursor);
+        self.value.replace_range(start..end, "");
+        self.cursor -= trailing_spaces + word;
+    }
+
+    pub fn clear(&mut self) {
+        self.value.clear();
+        self.cursor = 0;
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.value.chars().count());
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.value.chars().count();
+    }
+}
+
 pub struct InputBlock {
     value: String,
     title: String,
     is_selected: bool,
+    is_masked: bool,
+    cursor: usize,
     title_alignment: Alignment,
 }
 
 impl InputBlock {
     pub fn new(value: String, title: String, is_selected: bool) -> Self {
         Self {
+            cursor: value.chars().count(),
             value,
             title,
             is_selected,
+            is_masked: false,
             title_alignment: Alignment::Center,
         }
     }
 
+    pub fn from_state(state: &InputState, title: String, is_selected: bool) -> Self {
+        Self::new(state.value().to_string(), title, is_selected)
+            .with_cursor_position(state.cursor())
+    }
+
     pub fn with_title_alignment(self, title_alignment: Alignment) -> Self {
         Self {
-            value: self.value,
-            title: self.title,
-            is_selected: self.is_selected,
             title_alignment,
+            ..self
+        }
+    }
+
+    pub fn with_mask(self, is_masked: bool) -> Self {
+        Self { is_masked, ..self }
+    }
+
+    pub fn with_cursor_position(self, cursor: usize) -> Self {
+        Self { cursor, ..self }
+    }
+
+    // a masked value is drawn as the mask, the real length must not move the view
+    fn displayed_cursor(&self) -> usize {
+        if self.is_masked {
+            mask_value(&self.value).chars().count()
+        } else {
+            self.cursor
         }
     }
+
+    fn get_vertical_scroll(&self, section: &Rect) -> u16 {
+        let height = section.height.saturating_sub(2).max(1);
+        let row = self.displayed_cursor() as u16 / self.get_inner_area(section);
+        row.saturating_sub(height - 1)
+    }
 }
 
 impl Widget for InputBlock {
@@ -74,8 +218,21 @@ impl Widget for InputBlock {
             .direction(Direction::Horizontal)
             .constraints([Constraint::Fill(1)])
             .split(area);
-        let input_value = Paragraph::new(self.value.to_string())
-            .wrap(Wrap::default())
+        let value = if self.is_masked {
+            mask_value(&self.value)
+        } else {
+            self.value.to_string()
+        };
+        // wrap by chars instead of words so the cursor position can be computed
+        let width = self.get_inner_area(&input_sections[0]) as usize;
+        let lines = value
+            .chars()
+            .collect::<Vec<char>>()
+            .chunks(width)
+            .map(|chunk| Line::from(chunk.iter().collect::<String>()))
+            .collect::<Vec<Line>>();
+        let input_value = Paragraph::new(lines)
+            .scroll((self.get_vertical_scroll(&input_sections[0]), 0))
             .block(
                 Block::new()
                     .title(self.title.as_str())
@@ -92,90 +249,91 @@ impl Widget for InputBlock {
 impl WithCursor for InputBlock {
     fn get_x(&self, section: &Rect) -> u16 {
         let width = self.get_inner_area(section);
-        let value_len = self.value_length();
-        section.x + 1 + (value_len % width)
+        section.x + 1 + (self.displayed_cursor() as u16 % width)
     }
 
     fn get_y(&self, section: &Rect) -> u16 {
-        let starting_y = section.y;
         let width = self.get_inner_area(section);
-        let value_len = self.value_length();
-        let offset = (value_len + 1).div_ceil(width);
-        starting_y + offset
+        let row = self.displayed_cursor() as u16 / width;
+        section.y + 1 + row - self.get_vertical_scroll(section)
     }
 
+    // the cursor position would give away the length of a masked value
     fn is_visible(&self) -> bool {
-        self.is_selected
-    }
-
-    fn value_length(&self) -> u16 {
-        self.value.chars().count() as u16
+        self.is_selected && !self.is_masked
     }
 }
 
-pub struct Input {
-    value: String,
-    is_selected: bool,
-}
-impl Input {
-    pub fn new(value: String, is_selected: bool) -> Self {
-        Self { value, is_selected }
-    }
-}
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
 
-impl Widget for Input {
-    fn render(self, area: Rect, buf: &mut ratatui::prelude::Buffer)
-    where
-        Self: Sized,
-    {
-        let input_sections = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Fill(1)])
-            .split(area);
-        let width: usize = (input_sections[0].width - 2).into(); // need to remove 2 because of borders?
-        let value_len = self.value.chars().count();
-        let horizonta_scroll = if value_len > width - 1 {
-            value_len - width + 1
-        } else {
-            0
-        };
-        let input_value =
-            Paragraph::new(self.value.to_string()).scroll((0, horizonta_scroll as u16));
-        input_value.render(input_sections[0], buf);
-        self.with_cursor(input_sections[0], buf);
-    }
-}
+    use super::{InputBlock, InputState};
 
-impl WithCursor for Input {
-    fn get_x(&self, section: &Rect) -> u16 {
-        let width = self.get_inner_area(section);
-        let value_len = self.value_length();
-        let x = if value_len < width - 1 {
-            value_len % width
-        } else {
-            width - 1
-        };
-        x + section.x
-    }
+    #[test]
+    fn test_input_state_cursor_editing() {
+        let mut input = InputState::new("abcd".to_string());
+        assert_eq!(input.cursor(), 4);
 
-    fn get_y(&self, section: &Rect) -> u16 {
-        section.y
-    }
+        input.move_left();
+        input.move_left();
+        input.insert_char('x');
+        assert_eq!(input.value(), "abxcd");
+        assert_eq!(input.cursor(), 3);
 
-    fn is_visible(&self) -> bool {
-        self.is_selected
+        input.delete_char_forward();
+        assert_eq!(input.value(), "abxd");
+        input.delete_char();
+        assert_eq!(input.value(), "abd");
+        assert_eq!(input.cursor(), 2);
+
+        input.move_home();
+        input.delete_char();
+        assert_eq!(input.value(), "abd");
+        input.insert_str("éé");
+        assert_eq!(input.value(), "ééabd");
+        assert_eq!(input.cursor(), 2);
+
+        input.move_end();
+        input.move_right();
+        assert_eq!(input.cursor(), 5);
+        input.delete_char_forward();
+        assert_eq!(input.value(), "ééabd");
     }
 
-    fn value_length(&self) -> u16 {
-        self.value.chars().count() as u16
+    #[test]
+    fn test_input_state_key_events() {
+        let mut input = InputState::new("first second  ".to_string());
+
+        input.handle_key_event(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "first ");
+        input.handle_key_event(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "");
+
+        input.handle_key_event(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE));
+        input.handle_key_event(KeyEvent::new(KeyCode::Char('B'), KeyModifiers::SHIFT));
+        input.handle_key_event(KeyEvent::new(KeyCode::Home, KeyModifiers::NONE));
+        input.handle_key_event(KeyEvent::new(KeyCode::Delete, KeyModifiers::NONE));
+        assert_eq!(input.value(), "B");
+        input.handle_key_event(KeyEvent::new(KeyCode::Char('x'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "B");
+
+        input.handle_key_event(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "");
+        assert_eq!(input.cursor(), 0);
     }
-}
 
-fn fill<S: Into<Style>>(area: Rect, buf: &mut ratatui::prelude::Buffer, symbol: &str, style: S) {
-    let style = style.into();
-    for y in area.top()..area.bottom() {
-        for x in area.left()..area.right() {
-            buf.get_mut(x, y).set_symbol(symbol).set_style(style);
-        }
+    #[test]
+    fn test_long_masked_value_stays_in_view() {
+        let area = Rect::new(0, 0, 20, 3);
+        let mut buf = Buffer::empty(area);
+        InputBlock::new("x".repeat(500), "token".to_string(), false)
+            .with_mask(true)
+            .render(area, &mut buf);
+        let row = (0..area.width)
+            .map(|x| buf.get(x, 1).symbol().to_string())
+            .collect::<String>();
+        assert_eq!(row, "│********          │");
     }
 }
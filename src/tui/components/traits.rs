@@ -1,4 +1,3 @@
-use anyhow::Result;
 use crossterm::event::KeyEvent;
 use ratatui::{
     layout::{self, Rect},
@@ -13,9 +12,6 @@ pub struct ComponentProps {
 }
 
 pub trait Component {
-    fn init(&mut self) -> Result<()> {
-        Ok(())
-    }
     //   fn handle_events(&mut self, event: Option<Event>) -> Action {
     //     match event {
     //       Some(Event::Quit) => Action::Quit,
@@ -28,6 +24,7 @@ pub trait Component {
     //     }
     //   }
     fn handle_key_events(&mut self, key: KeyEvent);
+    fn handle_paste_events(&mut self, _text: &str) {}
     //   fn handle_mouse_events(&mut self, mouse: MouseEvent) -> Action {
     //     Action::Noop
     //   }
@@ -1,6 +1,6 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
-use super::components::traits::Component;
+use super::components::{input::InputState, traits::Component};
 
 #[derive(Debug)]
 pub struct S3liKeyEvent {
@@ -11,7 +11,7 @@ pub struct S3liKeyEvent {
 pub struct S3liOnChangeEvent {}
 
 pub type S3liEventListener<T> = (S3liKeyEvent, fn(&mut T));
-pub type S3liOnChangeEventListener<T> = (S3liOnChangeEvent, fn(&mut T, value: char), fn(&mut T));
+pub type S3liOnChangeEventListener<T> = (S3liOnChangeEvent, fn(&mut T) -> Option<&mut InputState>);
 
 #[derive(Debug)]
 pub enum EventListeners<T> {
@@ -50,8 +50,20 @@ impl S3liOnChangeEvent {
     }
 
     pub fn is_equal(&self, key_event: KeyEvent) -> bool {
-        !matches!(key_event.modifiers, KeyModifiers::CONTROL)
-            && matches!(key_event.code, KeyCode::Char(_) | KeyCode::Backspace)
+        match (key_event.code, key_event.modifiers) {
+            (KeyCode::Char('w') | KeyCode::Char('u'), KeyModifiers::CONTROL) => true,
+            (_, KeyModifiers::CONTROL) => false,
+            (code, _) => matches!(
+                code,
+                KeyCode::Char(_)
+                    | KeyCode::Backspace
+                    | KeyCode::Delete
+                    | KeyCode::Left
+                    | KeyCode::Right
+                    | KeyCode::Home
+                    | KeyCode::End
+            ),
+        }
     }
 }
 
@@ -68,16 +80,30 @@ where
         {
             match actual_event {
                 EventListeners::KeyEvent((_, listener)) => listener(self),
-                EventListeners::OnChangeEvent((_, add, delete)) => {
-                    if let KeyCode::Char(val) = event.code {
-                        add(self, val)
-                    } else if matches!(event.code, KeyCode::Backspace) {
-                        delete(self)
+                EventListeners::OnChangeEvent((_, get_input)) => {
+                    if let Some(input) = get_input(self) {
+                        input.handle_key_event(event);
                     }
                 }
             }
         }
     }
+    fn execute_paste(&mut self, text: &str) {
+        if let Some(EventListeners::OnChangeEvent((_, get_input))) = self
+            .get_event_listeners()
+            .iter()
+            .find(|eventlistener| matches!(eventlistener, EventListeners::OnChangeEvent(_)))
+        {
+            if let Some(input) = get_input(self) {
+                // inputs are single line, pasted line breaks and tabs are dropped
+                let text = text
+                    .chars()
+                    .filter(|val| !val.is_control())
+                    .collect::<String>();
+                input.insert_str(&text);
+            }
+        }
+    }
     fn extract_key_event_descriptions(&self) -> Vec<String> {
         self.get_event_listeners()
             .iter()
@@ -93,25 +119,25 @@ where
 mod tests {
     use crossterm::event::{KeyEventKind, KeyEventState, KeyModifiers};
 
-    use crate::tui::{components::traits::Component, key_event::S3liOnChangeEvent};
+    use crate::tui::{
+        components::{input::InputState, traits::Component},
+        key_event::S3liOnChangeEvent,
+    };
 
     use super::{EventListeners, ExecuteEventListener, S3liKeyEvent};
 
     struct MockComponent {
         listeners: Vec<EventListeners<Self>>,
-        state: String,
+        state: InputState,
     }
 
     impl MockComponent {
         fn update_state(&mut self) {
-            self.state = "update_from_test".to_string()
+            self.state = InputState::new("update_from_test".to_string())
         }
 
-        fn add_char(&mut self, value: char) {
-            self.state.push(value);
-        }
-        fn delete_char(&mut self) {
-            self.state.pop();
+        fn get_input(&mut self) -> Option<&mut InputState> {
+            Some(&mut self.state)
         }
     }
 
@@ -145,7 +171,7 @@ mod tests {
                 ),
                 MockComponent::update_state,
             ))],
-            state: "test".into(),
+            state: InputState::new("test".into()),
         };
 
         mock_component.handle_key_events(crossterm::event::KeyEvent {
@@ -155,7 +181,7 @@ mod tests {
             state: KeyEventState::NONE,
         });
 
-        assert_eq!(mock_component.state, "test".to_string());
+        assert_eq!(mock_component.state.value(), "test");
 
         mock_component.handle_key_events(crossterm::event::KeyEvent {
             code: crossterm::event::KeyCode::Char('t'),
@@ -164,7 +190,7 @@ mod tests {
             state: KeyEventState::NONE,
         });
 
-        assert_eq!(mock_component.state, "update_from_test".to_string());
+        assert_eq!(mock_component.state.value(), "update_from_test");
     }
 
     #[test]
@@ -172,10 +198,9 @@ mod tests {
         let mut mock_component = MockComponent {
             listeners: vec![EventListeners::OnChangeEvent((
                 S3liOnChangeEvent::new(),
-                MockComponent::add_char,
-                MockComponent::delete_char,
+                MockComponent::get_input,
             ))],
-            state: "test".into(),
+            state: InputState::new("test".into()),
         };
 
         mock_component.handle_key_events(crossterm::event::KeyEvent {
@@ -185,7 +210,7 @@ mod tests {
             state: KeyEventState::NONE,
         });
 
-        assert_eq!(mock_component.state, "test".to_string());
+        assert_eq!(mock_component.state.value(), "test");
 
         mock_component.handle_key_events(crossterm::event::KeyEvent {
             code: crossterm::event::KeyCode::Char('x'),
@@ -194,7 +219,7 @@ mod tests {
             state: KeyEventState::NONE,
         });
 
-        assert_eq!(mock_component.state, "testx".to_string());
+        assert_eq!(mock_component.state.value(), "testx");
 
         mock_component.handle_key_events(crossterm::event::KeyEvent {
             code: crossterm::event::KeyCode::Backspace,
@@ -203,6 +228,27 @@ mod tests {
             state: KeyEventState::NONE,
         });
 
-        assert_eq!(mock_component.state, "test".to_string());
+        assert_eq!(mock_component.state.value(), "test");
+    }
+
+    #[test]
+    fn test_execute_paste_onchange_event() {
+        let mut mock_component = MockComponent {
+            listeners: vec![
+                EventListeners::KeyEvent((
+                    S3liKeyEvent::new(
+                        vec![(crossterm::event::KeyCode::Char('t'), KeyModifiers::NONE)],
+                        "".into(),
+                    ),
+                    MockComponent::update_state,
+                )),
+                EventListeners::OnChangeEvent((S3liOnChangeEvent::new(), MockComponent::get_input)),
+            ],
+            state: InputState::new("test".into()),
+        };
+
+        mock_component.execute_paste("token\r\n");
+
+        assert_eq!(mock_component.state.value(), "testtoken");
     }
 }
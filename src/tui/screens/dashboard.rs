@@ -7,15 +7,17 @@ use tokio::sync::mpsc::UnboundedSender;
 use crate::{
     action::Action,
     store::{
-        explorer::{FileTree, Folder},
+        conflicts::FileConflict,
         notifications::types::Notification,
         state::{ui_state::UIState, DashboardComponents},
+        tasks::Task,
+        transfers::TransferEvent,
     },
     tui::{
         components::traits::{Component, ComponentProps},
         sections::{
-            accounts::Accounts, explorer::Explorer, hints::Hints, notifications::NotificationsUI,
-            sources::Sources,
+            accounts::Accounts, conflicts::ConflictsUI, diff::DiffUI, explorer::Explorer,
+            hints::Hints, notifications::NotificationsUI, sources::Sources, transfers::TransfersUI,
         },
     },
 };
@@ -26,6 +28,9 @@ pub struct Dashboard {
     accounts: Accounts,
     explorer: Explorer,
     notifications: NotificationsUI,
+    transfers: TransfersUI,
+    conflicts: ConflictsUI,
+    diff: DiffUI,
     hints: Hints,
     ui_tx: UnboundedSender<Action>,
     aside_constraints: [Constraint; 2],
@@ -36,7 +41,12 @@ impl Dashboard {
     where
         Self: Sized,
     {
-        let sources = Sources::new(&state.sources.available_sources, &None, ui_tx.clone());
+        let sources = Sources::new(
+            &state.sources.available_sources,
+            &None,
+            &state.sources.sources_regions,
+            ui_tx.clone(),
+        );
         let accounts = Accounts::new(
             &state.accounts.available_accounts,
             state.accounts.account_map.clone(),
@@ -45,7 +55,7 @@ impl Dashboard {
             ui_tx.clone(),
         );
 
-        let explorer = Explorer::new(None, None, ui_tx.clone());
+        let explorer = Explorer::new(None, None, state.conflict_policy, ui_tx.clone());
         let notifications = NotificationsUI::new(state.notifications.clone(), ui_tx.clone());
         let hints = Hints::default();
         Dashboard {
@@ -54,6 +64,9 @@ impl Dashboard {
             accounts,
             explorer,
             notifications,
+            transfers: TransfersUI::new(ui_tx.clone()),
+            conflicts: ConflictsUI::new(ui_tx.clone()),
+            diff: DiffUI::new(ui_tx.clone()),
             hints,
             ui_tx,
             aside_constraints: [Constraint::Length(3), Constraint::Fill(1)],
@@ -63,14 +76,34 @@ impl Dashboard {
         self.notifications.set_alert(Some(alert));
     }
 
-    pub fn refresh_explorer(&mut self, file_tree: FileTree, selected_folder: Option<Folder>) {
-        self.explorer.refresh(file_tree, selected_folder);
+    pub fn handle_task_started(&mut self, task: Task) {
+        self.notifications.add_task(task);
+    }
+
+    pub fn handle_task_finished(&mut self, task_id: usize) {
+        self.notifications.remove_task(task_id);
+    }
+
+    pub fn handle_transfer_event(&mut self, event: TransferEvent) {
+        if let TransferEvent::Completed { id }
+        | TransferEvent::Failed { id, .. }
+        | TransferEvent::Cancelled { id }
+        | TransferEvent::Skipped { id } = &event
+        {
+            self.conflicts.forget(*id);
+        }
+        self.transfers.handle_transfer_event(event);
+    }
+
+    pub fn handle_conflict(&mut self, conflict: FileConflict) {
+        self.conflicts.push(conflict);
     }
 
     pub fn refresh_components(mut self, state: &UIState) -> Self {
         let sources = Sources::new(
             &state.sources.available_sources,
             &state.sources.active_source,
+            &state.sources.sources_regions,
             self.ui_tx.clone(),
         );
         let accounts = Accounts::new(
@@ -84,6 +117,14 @@ impl Dashboard {
             state.explorer.file_tree.clone(),
             state.explorer.selected_folder.clone(),
         );
+        self.explorer
+            .set_download_destination(state.download_destination.clone());
+        self.explorer
+            .set_download_preview(state.download_preview.clone());
+        self.explorer
+            .set_active_source(state.sources.active_source.clone());
+        self.explorer.set_sync_preview(state.sync_preview.clone());
+        self.diff.refresh(state.diff.clone());
 
         self.notifications.refresh(state.notifications.clone());
         let aside_constraints =
@@ -100,21 +141,35 @@ impl Dashboard {
             accounts,
             explorer: self.explorer,
             notifications: self.notifications,
+            transfers: self.transfers,
+            conflicts: self.conflicts,
+            diff: self.diff,
             hints,
             ui_tx: self.ui_tx,
             aside_constraints,
         }
     }
     fn get_hints_for_selected_component(&self) -> Vec<String> {
+        if self.conflicts.has_pending() {
+            return self.conflicts.get_key_event_descriptions();
+        }
+        if self.diff.is_open() {
+            return self.diff.get_key_event_descriptions();
+        }
         match self.selected_component {
             DashboardComponents::Sources => self.sources.get_key_event_descriptions(),
             DashboardComponents::Accounts => self.accounts.get_key_event_descriptions(),
             DashboardComponents::Explorer => self.explorer.get_key_event_descriptions(),
+            DashboardComponents::Transfers => self.transfers.get_key_event_descriptions(),
         }
     }
     fn change_selected_component(&mut self) {
         let _ = self.ui_tx.send(Action::CycleSelectedComponent);
     }
+    // popups capture the keys while open, the transfers panel can't steal the focus
+    fn is_locked(&self) -> bool {
+        self.accounts.is_locked() || self.explorer.is_locked() || self.diff.is_open()
+    }
 }
 
 impl Component for Dashboard {
@@ -142,12 +197,21 @@ impl Component for Dashboard {
         else {
             panic!("aside should have 2 nested chunks")
         };
-        let [content, notification_section] = *Layout::default()
+        let transfers_height = if self.transfers.is_empty() {
+            0
+        } else {
+            self.transfers.height()
+        };
+        let [content, transfers_section, notification_section] = *Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Fill(1), Constraint::Length(3)])
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(transfers_height),
+                Constraint::Length(3),
+            ])
             .split(main)
         else {
-            panic!("layout needs to have 2 chunks")
+            panic!("layout needs to have 3 chunks")
         };
         self.explorer.render(
             f,
@@ -174,7 +238,18 @@ impl Component for Dashboard {
             }),
         );
 
+        if !self.transfers.is_empty() {
+            self.transfers.render(
+                f,
+                transfers_section,
+                Some(ComponentProps {
+                    selected: matches!(self.selected_component, DashboardComponents::Transfers),
+                }),
+            );
+        }
         self.notifications.render(f, notification_section, None);
+        self.diff.render(f, main, None);
+        self.conflicts.render(f, main, None);
         self.hints.render(f, hints, None);
     }
 
@@ -183,6 +258,25 @@ impl Component for Dashboard {
         if self.notifications.has_visible_alert() {
             return self.notifications.handle_key_events(key);
         }
+        if self.conflicts.has_pending() {
+            return self.conflicts.handle_key_events(key);
+        }
+        if self.diff.is_open() {
+            return self.diff.handle_key_events(key);
+        }
+        if let KeyEvent {
+            code: crossterm::event::KeyCode::Char('t'),
+            modifiers: crossterm::event::KeyModifiers::CONTROL,
+            ..
+        } = key
+        {
+            if !self.transfers.is_empty()
+                && !self.is_locked()
+                && !matches!(self.selected_component, DashboardComponents::Transfers)
+            {
+                return self.transfers.focus(self.selected_component.clone());
+            }
+        }
         match self.selected_component {
             DashboardComponents::Sources => match keycode {
                 crossterm::event::KeyCode::Left
@@ -192,6 +286,7 @@ impl Component for Dashboard {
                 _ => self.sources.handle_key_events(key),
             },
             DashboardComponents::Explorer => self.explorer.handle_key_events(key),
+            DashboardComponents::Transfers => self.transfers.handle_key_events(key),
             DashboardComponents::Accounts => match keycode {
                 crossterm::event::KeyCode::Left
                 | crossterm::event::KeyCode::Right
@@ -205,4 +300,16 @@ impl Component for Dashboard {
             },
         };
     }
+
+    fn handle_paste_events(&mut self, text: &str) {
+        if self.notifications.has_visible_alert() || self.diff.is_open() {
+            return;
+        }
+        match self.selected_component {
+            DashboardComponents::Sources => self.sources.handle_paste_events(text),
+            DashboardComponents::Explorer => self.explorer.handle_paste_events(text),
+            DashboardComponents::Accounts => self.accounts.handle_paste_events(text),
+            DashboardComponents::Transfers => {}
+        }
+    }
 }
@@ -1,5 +1,8 @@
 pub mod accounts;
+pub mod conflicts;
+pub mod diff;
 pub mod explorer;
 pub mod hints;
 pub mod notifications;
 pub mod sources;
+pub mod transfers;
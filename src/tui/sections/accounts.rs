@@ -149,4 +149,12 @@ impl Component for Accounts {
         self.component.handle_key_events(key);
         self.execute(key);
     }
+
+    fn handle_paste_events(&mut self, text: &str) {
+        if self.edit_popup.is_popup_open() {
+            self.edit_popup.handle_paste_events(text);
+        } else if self.region_popup.is_popup_open() {
+            self.region_popup.handle_paste_events(text);
+        }
+    }
 }
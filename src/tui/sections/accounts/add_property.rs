@@ -4,13 +4,18 @@ use ratatui::{
     widgets::Clear,
 };
 
-use crate::tui::{
-    components::{
-        input::InputBlock,
-        popup::WithPopup,
-        traits::{Component, ComponentProps, WithContainer},
+use crate::{
+    logger::LOGGER,
+    providers::is_secret_property,
+    tui::{
+        components::{
+            functions::copy_to_clipboard,
+            input::{InputBlock, InputState},
+            popup::WithPopup,
+            traits::{Component, ComponentProps, WithContainer},
+        },
+        key_event::{EventListeners, ExecuteEventListener, S3liKeyEvent, S3liOnChangeEvent},
     },
-    key_event::{EventListeners, ExecuteEventListener, S3liKeyEvent, S3liOnChangeEvent},
 };
 
 enum Selected {
@@ -19,9 +24,10 @@ enum Selected {
 }
 
 pub struct AddProperty {
-    name: String,
-    value: String,
+    name: InputState,
+    value: InputState,
     open: bool,
+    revealed: bool,
     selected: Selected,
     listeners: Vec<EventListeners<Self>>,
 }
@@ -32,6 +38,7 @@ impl AddProperty {
             name: Default::default(),
             value: Default::default(),
             open: false,
+            revealed: false,
             selected: Selected::Name,
             listeners: Self::register_listeners(),
         }
@@ -45,9 +52,13 @@ impl AddProperty {
     }
 
     pub fn get_property_to_add(&mut self) -> (String, Option<String>) {
-        let new_property = (self.name.clone(), Some(self.value.clone()));
+        let new_property = (
+            self.name.value().to_string(),
+            Some(self.value.value().to_string()),
+        );
         self.name = Default::default();
         self.value = Default::default();
+        self.revealed = false;
         new_property
     }
 
@@ -69,16 +80,19 @@ impl AddProperty {
             )),
             EventListeners::KeyEvent((
                 S3liKeyEvent::new(
-                    vec![(crossterm::event::KeyCode::Backspace, KeyModifiers::NONE)],
-                    "Delete: <Backspace>".into(),
+                    vec![(crossterm::event::KeyCode::Char('r'), KeyModifiers::CONTROL)],
+                    "Reveal: <C>-r".into(),
                 ),
-                Self::delete_char_from_selected,
+                Self::toggle_reveal,
             )),
-            EventListeners::OnChangeEvent((
-                S3liOnChangeEvent::new(),
-                Self::add_char_to_selected,
-                Self::delete_char_from_selected,
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('y'), KeyModifiers::CONTROL)],
+                    "Copy: <C>-y".into(),
+                ),
+                Self::copy_selected,
             )),
+            EventListeners::OnChangeEvent((S3liOnChangeEvent::new(), Self::get_selected_input)),
         ]
     }
 
@@ -86,17 +100,25 @@ impl AddProperty {
         self.close_popup();
     }
 
-    fn delete_char_from_selected(&mut self) {
-        match self.selected {
-            Selected::Name => self.name.pop(),
-            Selected::Value => self.value.pop(),
+    fn toggle_reveal(&mut self) {
+        self.revealed = !self.revealed;
+    }
+
+    fn copy_selected(&mut self) {
+        let value = match self.selected {
+            Selected::Name => &self.name,
+            Selected::Value => &self.value,
         };
+        if let Err(e) = copy_to_clipboard(value.value()) {
+            let _ = LOGGER.info(&format!("failed to copy property: {e:?}"));
+        }
     }
-    fn add_char_to_selected(&mut self, value: char) {
+
+    fn get_selected_input(&mut self) -> Option<&mut InputState> {
         match self.selected {
-            Selected::Name => self.name.push(value),
-            Selected::Value => self.value.push(value),
-        };
+            Selected::Name => Some(&mut self.name),
+            Selected::Value => Some(&mut self.value),
+        }
     }
 }
 
@@ -122,6 +144,9 @@ impl Component for AddProperty {
     fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
         self.execute(key);
     }
+    fn handle_paste_events(&mut self, text: &str) {
+        self.execute_paste(text);
+    }
 
     fn render(
         &mut self,
@@ -142,19 +167,20 @@ impl Component for AddProperty {
             .margin(1)
             .split(vertical[1]);
 
-        let input_name = InputBlock::new(
-            self.name.clone(),
+        let input_name = InputBlock::from_state(
+            &self.name,
             "Name".to_string(),
             matches!(self.selected, Selected::Name),
         );
 
         f.render_widget(input_name, input_container[0]);
 
-        let input_value = InputBlock::new(
-            self.value.clone(),
+        let input_value = InputBlock::from_state(
+            &self.value,
             "Value".to_string(),
             matches!(self.selected, Selected::Value),
-        );
+        )
+        .with_mask(is_secret_property(self.name.value()) && !self.revealed);
 
         f.render_widget(input_value, input_container[1]);
     }
@@ -10,9 +10,12 @@ use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     action::Action,
+    logger::LOGGER,
+    providers::is_secret_property,
     tui::{
         components::{
-            input::InputBlock,
+            functions::copy_to_clipboard,
+            input::{InputBlock, InputState},
             popup::WithPopup,
             traits::{Component, ComponentProps, WithContainer},
         },
@@ -25,9 +28,10 @@ use super::add_property::AddProperty;
 pub struct EditAccount {
     open: bool,
     properties: HashMap<String, Option<String>>,
-    new_properties: Vec<(String, Option<String>)>,
+    new_properties: Vec<(String, Option<InputState>)>,
     account_to_edit: Option<String>,
     selected_idx: usize,
+    revealed: Vec<usize>,
     ui_tx: UnboundedSender<Action>,
     add_property: AddProperty,
     listeners: Vec<EventListeners<Self>>,
@@ -42,13 +46,14 @@ impl EditAccount {
             new_properties: vec![],
             ui_tx: ui_tx.clone(),
             selected_idx: 0,
+            revealed: vec![],
             add_property: AddProperty::new(),
             listeners: Self::register_listeners(),
         }
     }
 
-    fn add_to_properties(&mut self, property: (String, Option<String>)) -> Result<bool> {
-        self.new_properties.push(property);
+    fn add_to_properties(&mut self, (key, value): (String, Option<String>)) -> Result<bool> {
+        self.new_properties.push((key, value.map(InputState::new)));
         Ok(true)
     }
 
@@ -62,9 +67,10 @@ impl EditAccount {
         self.new_properties = properties
             .clone()
             .iter()
-            .map(|(key, value)| (key.to_string(), value.to_owned()))
-            .collect::<Vec<(String, Option<String>)>>();
+            .map(|(key, value)| (key.to_string(), value.to_owned().map(InputState::new)))
+            .collect::<Vec<(String, Option<InputState>)>>();
         self.selected_idx = 0;
+        self.revealed = vec![];
     }
 
     fn register_listeners() -> Vec<EventListeners<Self>> {
@@ -97,11 +103,21 @@ impl EditAccount {
                 ),
                 Self::cycle_properties,
             )),
-            EventListeners::OnChangeEvent((
-                S3liOnChangeEvent::new(),
-                Self::add_char,
-                Self::delete_char,
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('r'), KeyModifiers::CONTROL)],
+                    "Reveal: <C>-r".into(),
+                ),
+                Self::toggle_reveal,
             )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('y'), KeyModifiers::CONTROL)],
+                    "Copy: <C>-y".into(),
+                ),
+                Self::copy_selected,
+            )),
+            EventListeners::OnChangeEvent((S3liOnChangeEvent::new(), Self::get_selected_input)),
         ]
     }
 
@@ -122,7 +138,12 @@ impl EditAccount {
             let new_properties_hash_map = self
                 .new_properties
                 .iter()
-                .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                .map(|(key, value)| {
+                    (
+                        key.to_owned(),
+                        value.as_ref().map(|value| value.value().to_string()),
+                    )
+                })
                 .collect::<HashMap<String, Option<String>>>();
             let _ = self.ui_tx.send(Action::EditCredentials(
                 self.account_to_edit.clone().unwrap(),
@@ -137,20 +158,32 @@ impl EditAccount {
             self.selected_idx += 1;
         }
     }
-    fn delete_char(&mut self) {
-        if let Some(item) = self.new_properties.get_mut(self.selected_idx) {
-            if let Some(act_val) = item.1.as_mut() {
-                act_val.pop();
-            }
+    fn toggle_reveal(&mut self) {
+        if let Some(position) = self
+            .revealed
+            .iter()
+            .position(|idx| *idx == self.selected_idx)
+        {
+            self.revealed.remove(position);
+        } else {
+            self.revealed.push(self.selected_idx);
         }
     }
-    fn add_char(&mut self, value: char) {
-        if let Some(item) = self.new_properties.get_mut(self.selected_idx) {
-            if let Some(prop) = item.1.as_mut() {
-                prop.push(value)
+    fn copy_selected(&mut self) {
+        if let Some((key, Some(value))) = self.new_properties.get(self.selected_idx) {
+            if let Err(e) = copy_to_clipboard(value.value()) {
+                let _ = LOGGER.info(&format!("failed to copy property {key}: {e:?}"));
             }
         }
     }
+    fn is_masked(&self, idx: usize, key: &str) -> bool {
+        is_secret_property(key) && !self.revealed.contains(&idx)
+    }
+    fn get_selected_input(&mut self) -> Option<&mut InputState> {
+        self.new_properties
+            .get_mut(self.selected_idx)
+            .and_then(|(_, value)| value.as_mut())
+    }
 }
 
 impl WithContainer<'_> for EditAccount {}
@@ -230,9 +263,9 @@ impl Component for EditAccount {
                 .for_each(|(idx, ((key, value), property_area))| {
                     if let Some(value) = value {
                         let is_selected = idx == self.selected_idx;
-                        let input =
-                            InputBlock::new(value.to_string(), key.to_string(), is_selected)
-                                .with_title_alignment(ratatui::layout::Alignment::Right);
+                        let input = InputBlock::from_state(value, key.to_string(), is_selected)
+                            .with_title_alignment(ratatui::layout::Alignment::Right)
+                            .with_mask(self.is_masked(idx, key));
                         f.render_widget(input, *property_area);
                     }
                 });
@@ -241,4 +274,12 @@ impl Component for EditAccount {
             }
         }
     }
+
+    fn handle_paste_events(&mut self, text: &str) {
+        if self.add_property.is_popup_open() {
+            self.add_property.handle_paste_events(text);
+            return;
+        }
+        self.execute_paste(text);
+    }
 }
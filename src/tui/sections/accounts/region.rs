@@ -1,15 +1,17 @@
 use crossterm::event::KeyModifiers;
 use ratatui::{
     layout::{Constraint, Direction, Layout},
-    widgets::Clear,
+    style::{Color, Style},
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, ListState, Paragraph},
 };
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     action::Action,
+    providers::{filter_regions, is_known_region},
     tui::{
         components::{
-            input::InputBlock,
+            input::{InputBlock, InputState},
             popup::WithPopup,
             traits::{Component, ComponentProps, WithContainer},
         },
@@ -20,7 +22,8 @@ use crate::{
 pub struct Region {
     pub open: bool,
     pub region: String,
-    pub new_region: String,
+    pub new_region: InputState,
+    list_state: ListState,
     ui_tx: UnboundedSender<Action>,
     listeners: Vec<EventListeners<Self>>,
 }
@@ -31,7 +34,8 @@ impl Region {
             ui_tx,
             open: false,
             region: region.clone(),
-            new_region: region.clone(),
+            new_region: InputState::default(),
+            list_state: ListState::default(),
             listeners: Self::register_listeners(),
         }
     }
@@ -52,38 +56,91 @@ impl Region {
                 ),
                 Self::confirm,
             )),
-            EventListeners::OnChangeEvent((
-                S3liOnChangeEvent::new(),
-                Self::add_char,
-                Self::delete_char,
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Up, KeyModifiers::NONE)],
+                    "Move up: <Up>".into(),
+                ),
+                Self::move_up,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Down, KeyModifiers::NONE)],
+                    "Move down: <Down>".into(),
+                ),
+                Self::move_down,
             )),
+            EventListeners::OnChangeEvent((S3liOnChangeEvent::new(), Self::get_input)),
         ]
     }
 
+    fn get_filtered_regions(&self) -> Vec<&'static str> {
+        filter_regions(self.new_region.value())
+    }
+
+    // a region picked from the list wins over the typed text, the typed
+    // text is used as is when nothing is highlighted
+    fn get_region_to_confirm(&self) -> String {
+        self.list_state
+            .selected()
+            .and_then(|idx| self.get_filtered_regions().get(idx).copied())
+            .map(|region| region.to_string())
+            .unwrap_or_else(|| self.new_region.value().to_string())
+    }
+
     fn exit_component(&mut self) {
-        self.open = false;
-        if !self.new_region.eq(&self.region) {
-            self.new_region.clone_from(&self.region);
-        }
+        self.close_popup();
     }
     fn confirm(&mut self) {
+        let region = self.get_region_to_confirm();
+        if region.is_empty() {
+            return;
+        }
         // send region to state with ui_tx
-        let _ = self
-            .ui_tx
-            .send(Action::ChangeRegion(self.new_region.clone()));
-        self.open = false;
+        let _ = self.ui_tx.send(Action::ChangeRegion(region));
+        self.close_popup();
+    }
+    fn move_up(&mut self) {
+        let regions_len = self.get_filtered_regions().len();
+        if regions_len == 0 {
+            return;
+        }
+        let idx = match self.list_state.selected() {
+            Some(0) | None => regions_len - 1,
+            Some(idx) => idx - 1,
+        };
+        self.list_state.select(Some(idx));
     }
-    fn delete_char(&mut self) {
-        self.new_region.pop();
+    fn move_down(&mut self) {
+        let regions_len = self.get_filtered_regions().len();
+        if regions_len == 0 {
+            return;
+        }
+        let idx = match self.list_state.selected() {
+            Some(idx) if idx + 1 < regions_len => idx + 1,
+            _ => 0,
+        };
+        self.list_state.select(Some(idx));
+    }
+    fn get_input(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.new_region)
     }
-    fn add_char(&mut self, value: char) {
-        self.new_region.push(value);
+    fn reset_selection_on_change(&mut self, previous_value: &str) {
+        if self.new_region.value() != previous_value {
+            self.list_state.select(None);
+        }
     }
 }
 
 impl WithPopup for Region {
     fn set_popup_state(&mut self, open: bool) {
         self.open = open;
+        self.new_region.clear();
+        self.list_state.select(
+            self.get_filtered_regions()
+                .iter()
+                .position(|region| *region == self.region),
+        );
     }
 
     fn get_popup_state(&self) -> bool {
@@ -108,7 +165,11 @@ impl Component for Region {
     ) {
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Fill(1), Constraint::Max(3), Constraint::Fill(1)])
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Length(16),
+                Constraint::Fill(1),
+            ])
             .split(f.size());
         let center_section = Layout::default()
             .direction(Direction::Horizontal)
@@ -118,12 +179,59 @@ impl Component for Region {
                 Constraint::Fill(2),
             ])
             .split(layout[1])[1];
+        let [input_section, list_section, warning_section] = *Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(1),
+            ])
+            .split(center_section)
+        else {
+            panic!("region popup needs to have 3 chunks")
+        };
+
+        let input = InputBlock::from_state(&self.new_region, "Region".to_string(), true);
+        let regions = self
+            .get_filtered_regions()
+            .into_iter()
+            .map(|region| {
+                let style = if region == self.region {
+                    Style::default().fg(Color::Green)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(region).style(style)
+            })
+            .collect::<Vec<ListItem>>();
+        let list = List::new(regions)
+            .block(
+                Block::default()
+                    .title("Known regions")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded),
+            )
+            .highlight_style(Style::default().fg(Color::Green).bg(Color::LightBlue));
 
-        let input = InputBlock::new(self.new_region.to_string(), "Region".to_string(), true);
         f.render_widget(Clear, center_section);
-        f.render_widget(input, center_section);
+        f.render_widget(input, input_section);
+        f.render_stateful_widget(list, list_section, &mut self.list_state);
+
+        let region_to_confirm = self.get_region_to_confirm();
+        if !region_to_confirm.is_empty() && !is_known_region(&region_to_confirm) {
+            let warning = Paragraph::new(format!("{region_to_confirm} is not a known region"))
+                .style(Style::default().fg(Color::Yellow));
+            f.render_widget(warning, warning_section);
+        }
     }
     fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
+        let previous_value = self.new_region.value().to_string();
         self.execute(key);
+        self.reset_selection_on_change(&previous_value);
+    }
+    fn handle_paste_events(&mut self, text: &str) {
+        let previous_value = self.new_region.value().to_string();
+        self.execute_paste(text);
+        self.reset_selection_on_change(&previous_value);
     }
 }
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Style},
+    widgets::{block::Title, Block, BorderType, Borders, Clear, Paragraph, Wrap},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::Action,
+    store::conflicts::{ConflictResolution, FileConflict},
+    tui::{
+        components::traits::{Component, ComponentProps},
+        key_event::{EventListeners, ExecuteEventListener, S3liKeyEvent},
+    },
+};
+
+// files of downloads using the `ask` policy wait here for the user to choose,
+// one question is shown at a time
+pub struct ConflictsUI {
+    pending: VecDeque<FileConflict>,
+    ui_tx: UnboundedSender<Action>,
+    listeners: Vec<EventListeners<Self>>,
+}
+
+impl ConflictsUI {
+    pub fn new(ui_tx: UnboundedSender<Action>) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            ui_tx,
+            listeners: Self::register_listeners(),
+        }
+    }
+
+    fn register_listeners() -> Vec<EventListeners<Self>> {
+        vec![
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(KeyCode::Char('o'), KeyModifiers::NONE)],
+                    "Overwrite: o".into(),
+                ),
+                Self::overwrite,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![
+                        (KeyCode::Char('s'), KeyModifiers::NONE),
+                        (KeyCode::Esc, KeyModifiers::NONE),
+                    ],
+                    "Skip: s or <Esc>".into(),
+                ),
+                Self::skip,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(KeyCode::Char('r'), KeyModifiers::NONE)],
+                    "Rename: r".into(),
+                ),
+                Self::rename,
+            )),
+        ]
+    }
+
+    pub fn push(&mut self, conflict: FileConflict) {
+        self.pending.push_back(conflict);
+    }
+
+    // the transfer finished without an answer, e.g. it has been cancelled
+    pub fn forget(&mut self, transfer_id: usize) {
+        self.pending
+            .retain(|conflict| conflict.transfer_id != transfer_id);
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn resolve(&mut self, resolution: ConflictResolution) {
+        if let Some(conflict) = self.pending.pop_front() {
+            let _ = self
+                .ui_tx
+                .send(Action::ResolveConflict(conflict.transfer_id, resolution));
+        }
+    }
+
+    fn overwrite(&mut self) {
+        self.resolve(ConflictResolution::Overwrite);
+    }
+
+    fn skip(&mut self) {
+        self.resolve(ConflictResolution::Skip);
+    }
+
+    fn rename(&mut self) {
+        self.resolve(ConflictResolution::Rename);
+    }
+
+    pub fn get_key_event_descriptions(&self) -> Vec<String> {
+        self.extract_key_event_descriptions()
+    }
+}
+
+impl ExecuteEventListener for ConflictsUI {
+    fn get_event_listeners(&self) -> &Vec<EventListeners<Self>> {
+        &self.listeners
+    }
+}
+
+impl Component for ConflictsUI {
+    fn render(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        _area: ratatui::prelude::Rect,
+        _props: Option<ComponentProps>,
+    ) {
+        let Some(conflict) = self.pending.front() else {
+            return;
+        };
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Max(5), Constraint::Fill(1)])
+            .split(f.size());
+        let center_section = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Fill(2),
+                Constraint::Fill(1),
+            ])
+            .split(layout[1])[1];
+        let mut container = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title("File already exists")
+            .border_style(Style::default().fg(Color::Yellow));
+        if self.pending.len() > 1 {
+            container = container.title(
+                Title::from(format!("{} more", self.pending.len() - 1)).alignment(Alignment::Right),
+            );
+        }
+        let question = Paragraph::new(format!(
+            "{}\n(o)verwrite, (s)kip or (r)ename",
+            conflict.name
+        ))
+        .block(container)
+        .wrap(Wrap::default());
+        f.render_widget(Clear, center_section);
+        f.render_widget(question, center_section);
+    }
+
+    fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        self.execute(key)
+    }
+}
@@ -0,0 +1,228 @@
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{block::Title, Block, BorderType, Borders, Clear, Paragraph, Wrap},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::Action,
+    store::diff::{Diff, DiffEntry, DiffKind, DiffResult},
+    tui::{
+        components::{
+            functions::format_bytes,
+            traits::{Component, ComponentProps},
+        },
+        key_event::{EventListeners, ExecuteEventListener, S3liKeyEvent},
+    },
+};
+
+// read only comparison of two locations, shown over the dashboard until closed
+pub struct DiffUI {
+    result: Option<DiffResult>,
+    scroll: u16,
+    ui_tx: UnboundedSender<Action>,
+    listeners: Vec<EventListeners<Self>>,
+}
+
+impl DiffUI {
+    pub fn new(ui_tx: UnboundedSender<Action>) -> Self {
+        Self {
+            result: None,
+            scroll: 0,
+            ui_tx,
+            listeners: Self::register_listeners(),
+        }
+    }
+
+    fn register_listeners() -> Vec<EventListeners<Self>> {
+        vec![
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![
+                        (KeyCode::Char('k'), KeyModifiers::NONE),
+                        (KeyCode::Up, KeyModifiers::NONE),
+                    ],
+                    "Scroll up: k or <Up>".into(),
+                ),
+                Self::scroll_up,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![
+                        (KeyCode::Char('j'), KeyModifiers::NONE),
+                        (KeyCode::Down, KeyModifiers::NONE),
+                    ],
+                    "Scroll down: j or <Down>".into(),
+                ),
+                Self::scroll_down,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![
+                        (KeyCode::Char('q'), KeyModifiers::NONE),
+                        (KeyCode::Esc, KeyModifiers::NONE),
+                    ],
+                    "Close: q or <Esc>".into(),
+                ),
+                Self::close,
+            )),
+        ]
+    }
+
+    // a new comparison starts from the top
+    pub fn refresh(&mut self, result: Option<DiffResult>) {
+        let is_new = match (&self.result, &result) {
+            (Some(current), Some(result)) => {
+                current.left != result.left || current.right != result.right
+            }
+            _ => true,
+        };
+        if is_new {
+            self.scroll = 0;
+        }
+        self.result = result;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.result.is_some()
+    }
+
+    fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_add(1);
+    }
+
+    fn close(&mut self) {
+        self.result = None;
+        let _ = self.ui_tx.send(Action::CloseDiff);
+    }
+
+    pub fn get_key_event_descriptions(&self) -> Vec<String> {
+        self.extract_key_event_descriptions()
+    }
+
+    fn totals(diff: &Diff) -> String {
+        let total = |kind| {
+            let (count, bytes) = diff.total(kind);
+            format!("{count} ({})", format_bytes(bytes))
+        };
+        let mut totals = format!(
+            "< only left: {}  > only right: {}  ~ changed: {}  ? same size, not comparable: {}  = identical: {}",
+            total(DiffKind::OnlyLeft),
+            total(DiffKind::OnlyRight),
+            total(DiffKind::Changed),
+            total(DiffKind::Unverified),
+            diff.same
+        );
+        if !diff.rejected.is_empty() {
+            totals.push_str(&format!("  ignored: {}", diff.rejected.len()));
+        }
+        totals
+    }
+
+    fn entry_line(entry: &DiffEntry) -> Line<'static> {
+        let size = |size: Option<u64>| size.map(format_bytes).unwrap_or_default();
+        let (symbol, color, sizes) = match entry.kind {
+            DiffKind::OnlyLeft => ("<", Color::Green, size(entry.left_size)),
+            DiffKind::OnlyRight => (">", Color::Red, size(entry.right_size)),
+            DiffKind::Changed => (
+                "~",
+                Color::Yellow,
+                format!("{} -> {}", size(entry.left_size), size(entry.right_size)),
+            ),
+            DiffKind::Unverified => ("?", Color::Gray, size(entry.left_size)),
+        };
+        Line::from(vec![
+            Span::styled(
+                format!("{symbol} {}", entry.path),
+                Style::default().fg(color),
+            ),
+            Span::raw(format!("  {sizes}")),
+        ])
+    }
+}
+
+impl ExecuteEventListener for DiffUI {
+    fn get_event_listeners(&self) -> &Vec<EventListeners<Self>> {
+        &self.listeners
+    }
+}
+
+impl Component for DiffUI {
+    fn render(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        area: ratatui::prelude::Rect,
+        _props: Option<ComponentProps>,
+    ) {
+        let Some(result) = &self.result else {
+            return;
+        };
+        let container = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(format!(
+                "{} <-> {}",
+                result.left.describe(),
+                result.right.describe()
+            ))
+            .title_alignment(Alignment::Center)
+            .border_style(Style::default().fg(Color::Green));
+        let [summary_section, entries_section] = *Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(2), Constraint::Fill(1)])
+            .margin(1)
+            .split(area)
+        else {
+            panic!("layout needs to have 2 chunks")
+        };
+        let (summary, lines) = match &result.diff {
+            Ok(diff) if diff.entries.is_empty() && diff.rejected.is_empty() => {
+                (format!("No difference, {}", Self::totals(diff)), vec![])
+            }
+            Ok(diff) => {
+                let mut lines = diff
+                    .entries
+                    .iter()
+                    .map(Self::entry_line)
+                    .collect::<Vec<_>>();
+                lines.extend(
+                    diff.rejected
+                        .iter()
+                        .map(|(key, reason)| Line::from(format!("! {key}: {reason}"))),
+                );
+                (Self::totals(diff), lines)
+            }
+            Err(e) => (format!("Failed to compare: {e}"), vec![]),
+        };
+        self.scroll = self
+            .scroll
+            .min(lines.len().saturating_sub(1).try_into().unwrap_or(u16::MAX));
+        let position = Title::from(format!("{} entries", lines.len()))
+            .position(ratatui::widgets::block::Position::Bottom)
+            .alignment(Alignment::Right);
+        f.render_widget(Clear, area);
+        f.render_widget(container.title(position), area);
+        f.render_widget(
+            Paragraph::new(summary).wrap(Wrap::default()),
+            summary_section,
+        );
+        f.render_widget(
+            Paragraph::new(lines).scroll((self.scroll, 0)),
+            entries_section,
+        );
+    }
+
+    fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        self.execute(key)
+    }
+}
@@ -1,5 +1,8 @@
+mod compare;
 mod download;
+mod sync;
 
+use compare::CompareFolder;
 use crossterm::event::{KeyEventKind, KeyModifiers};
 use download::Download;
 use ratatui::{
@@ -7,14 +10,18 @@ use ratatui::{
     text::{Line, Span},
     widgets::{List, ListItem, ListState},
 };
+use sync::SyncFolder;
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     action::Action,
     logger::LOGGER,
     store::{
+        conflicts::ConflictPolicy,
         explorer::{FileTree, Folder, TreeItem},
+        sources::traits::DownloadPreview,
         state::DashboardComponents,
+        sync::SyncPreview,
     },
     tui::{
         components::{
@@ -33,11 +40,12 @@ use crate::{
 #[derive(Debug)]
 pub struct Explorer {
     list_state: ListState,
-    selected_file: Option<String>,
     file_tree: Vec<TreeItem>,
     ui_tx: UnboundedSender<Action>,
     current_folder_idx: Option<usize>,
     download_component: Download,
+    sync_component: SyncFolder,
+    compare_component: CompareFolder,
     mode: ListMode,
     selection: Vec<usize>,
     listeners: Vec<EventListeners<Self>>,
@@ -47,6 +55,7 @@ impl Explorer {
     pub fn new(
         file_tree: Option<FileTree>,
         current_folder: Option<Folder>,
+        conflict_policy: ConflictPolicy,
         ui_tx: UnboundedSender<Action>,
     ) -> Self {
         let file_tree_vec = file_tree
@@ -75,11 +84,12 @@ impl Explorer {
         list_state.select(current_folder_idx);
         Self {
             list_state,
-            selected_file: None,
             file_tree: file_tree_vec,
             ui_tx: ui_tx.clone(),
             current_folder_idx,
-            download_component: Download::new(ui_tx.clone()),
+            download_component: Download::new(ui_tx.clone(), conflict_policy),
+            sync_component: SyncFolder::new(ui_tx.clone()),
+            compare_component: CompareFolder::new(ui_tx.clone()),
             selection: vec![],
             mode: ListMode::Normal,
             listeners: Self::register_listeners(),
@@ -114,6 +124,32 @@ impl Explorer {
         }
     }
 
+    pub fn set_download_destination(&mut self, destination: String) {
+        self.sync_component.set_default_target(destination.clone());
+        self.compare_component
+            .set_default_target(destination.clone());
+        self.download_component.set_default_destination(destination);
+    }
+
+    pub fn set_download_preview(&mut self, preview: Option<DownloadPreview>) {
+        self.download_component.set_preview(preview);
+    }
+
+    pub fn set_active_source(&mut self, source: Option<String>) {
+        self.compare_component.set_source(source.clone());
+        self.sync_component.set_source(source);
+    }
+
+    pub fn set_sync_preview(&mut self, preview: Option<SyncPreview>) {
+        self.sync_component.set_preview(preview);
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.download_component.is_popup_open()
+            || self.sync_component.is_popup_open()
+            || self.compare_component.is_popup_open()
+    }
+
     pub fn set_active_idx(&mut self, active_idx: Option<usize>) {
         self.current_folder_idx = active_idx;
     }
@@ -175,6 +211,20 @@ impl Explorer {
                 ),
                 Self::init_download,
             )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('s'), KeyModifiers::NONE)],
+                    "Sync with a local directory: s".into(),
+                ),
+                Self::init_sync,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('c'), KeyModifiers::NONE)],
+                    "Compare with: c".into(),
+                ),
+                Self::init_compare,
+            )),
         ]
     }
 
@@ -187,7 +237,7 @@ impl Explorer {
         if self.selection.is_empty() {
             self.mode = ListMode::Normal;
         }
-        LOGGER.info(&format!("selection: {:?}", self.selection));
+        let _ = LOGGER.info(&format!("selection: {:?}", self.selection));
     }
     fn cancel(&mut self) {
         if matches!(self.mode, ListMode::Selection | ListMode::Multi) {
@@ -267,9 +317,37 @@ impl Explorer {
         self.download_component.init(files);
     }
 
+    // the folder under the cursor, or the one containing the file under it
+    fn selected_prefix(&self) -> String {
+        let selected_item = self
+            .get_list_state_selected()
+            .and_then(|idx| self.file_tree.get(idx));
+        let folder = match selected_item {
+            Some(TreeItem::Folder(folder, _)) => Some(folder),
+            Some(TreeItem::File(_, parent)) => parent.as_ref(),
+            None => None,
+        };
+        match folder {
+            Some(folder) if folder.name != "/" => folder.name.clone(),
+            _ => String::new(),
+        }
+    }
+
+    fn init_sync(&mut self) {
+        self.sync_component.init(self.selected_prefix());
+    }
+
+    fn init_compare(&mut self) {
+        self.compare_component.init(self.selected_prefix());
+    }
+
     pub fn get_key_event_descriptions(&self) -> Vec<String> {
         if self.download_component.is_popup_open() {
             self.download_component.extract_key_event_descriptions()
+        } else if self.sync_component.is_popup_open() {
+            self.sync_component.extract_key_event_descriptions()
+        } else if self.compare_component.is_popup_open() {
+            self.compare_component.extract_key_event_descriptions()
         } else {
             self.extract_key_event_descriptions()
         }
@@ -344,9 +422,27 @@ impl Component for Explorer {
             self.download_component.handle_key_events(key);
             return;
         }
+        if self.sync_component.is_popup_open() {
+            self.sync_component.handle_key_events(key);
+            return;
+        }
+        if self.compare_component.is_popup_open() {
+            self.compare_component.handle_key_events(key);
+            return;
+        }
         self.execute(key);
     }
 
+    fn handle_paste_events(&mut self, text: &str) {
+        if self.download_component.is_popup_open() {
+            self.download_component.handle_paste_events(text);
+        } else if self.sync_component.is_popup_open() {
+            self.sync_component.handle_paste_events(text);
+        } else if self.compare_component.is_popup_open() {
+            self.compare_component.handle_paste_events(text);
+        }
+    }
+
     fn render(
         &mut self,
         f: &mut ratatui::prelude::Frame,
@@ -366,12 +462,10 @@ impl Component for Explorer {
                     TreeItem::Folder(folder, _) => {
                         let arrow_char = match file_tree_iterator.peek() {
                             Some((_, TreeItem::Folder(_, Some(parent))))
-                            | Some((_, TreeItem::File(_, Some(parent)))) => {
-                                if parent.name == folder.name {
-                                    "▼"
-                                } else {
-                                    "▶"
-                                }
+                            | Some((_, TreeItem::File(_, Some(parent))))
+                                if parent.name == folder.name =>
+                            {
+                                "▼"
                             }
                             _ => "▶",
                         };
@@ -410,7 +504,13 @@ impl Component for Explorer {
         f.render_stateful_widget(list, area, &mut self.list_state);
 
         if self.download_component.is_popup_open() {
-            self.download_component.render(f, area, props);
+            self.download_component.render(f, area, props.clone());
+        }
+        if self.sync_component.is_popup_open() {
+            self.sync_component.render(f, area, props.clone());
+        }
+        if self.compare_component.is_popup_open() {
+            self.compare_component.render(f, area, props);
         }
     }
 }
@@ -0,0 +1,193 @@
+use crossterm::event::KeyModifiers;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    widgets::Clear,
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::Action,
+    store::sync::SyncLocation,
+    tui::{
+        components::{
+            functions::complete_directory,
+            input::{InputBlock, InputState},
+            popup::WithPopup,
+            traits::{Component, ComponentProps, WithContainer},
+        },
+        key_event::{EventListeners, ExecuteEventListener, S3liKeyEvent, S3liOnChangeEvent},
+    },
+};
+
+// asks for the location the selected folder is compared with, the result is
+// shown by the diff section
+#[derive(Debug)]
+pub struct CompareFolder {
+    pub open: bool,
+    // key of the folder, empty for the root of the bucket
+    prefix: String,
+    source: Option<String>,
+    target: InputState,
+    // last destination used with the active source
+    default_target: String,
+    ui_tx: UnboundedSender<Action>,
+    listeners: Vec<EventListeners<Self>>,
+}
+
+impl CompareFolder {
+    pub fn new(ui_tx: UnboundedSender<Action>) -> Self {
+        Self {
+            open: false,
+            prefix: String::new(),
+            source: None,
+            target: InputState::default(),
+            default_target: String::new(),
+            ui_tx,
+            listeners: Self::register_listeners(),
+        }
+    }
+
+    pub fn init(&mut self, prefix: String) {
+        self.prefix = prefix;
+        self.target = InputState::new(self.default_target.clone());
+        self.open = true;
+    }
+
+    pub fn set_default_target(&mut self, target: String) {
+        self.default_target = target;
+    }
+
+    pub fn set_source(&mut self, source: Option<String>) {
+        self.source = source;
+    }
+
+    fn register_listeners() -> Vec<EventListeners<Self>> {
+        vec![
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Esc, KeyModifiers::NONE)],
+                    "Cancel: <Esc>".into(),
+                ),
+                Self::exit_component,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Enter, KeyModifiers::NONE)],
+                    "Compare: <Enter>".into(),
+                ),
+                Self::confirm,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Tab, KeyModifiers::NONE)],
+                    "Complete path: <Tab>".into(),
+                ),
+                Self::complete_target,
+            )),
+            EventListeners::OnChangeEvent((S3liOnChangeEvent::new(), Self::get_current_input)),
+        ]
+    }
+
+    fn exit_component(&mut self) {
+        self.open = false;
+    }
+
+    fn confirm(&mut self) {
+        let Some(source) = self.source.clone() else {
+            return;
+        };
+        if self.target.value().trim().is_empty() {
+            return;
+        }
+        let folder = SyncLocation::Folder {
+            source,
+            prefix: self.prefix.clone(),
+        };
+        let _ = self.ui_tx.send(Action::CompareFolders(
+            folder,
+            SyncLocation::parse(self.target.value()),
+        ));
+        self.open = false;
+    }
+
+    // only local directories are completed
+    fn complete_target(&mut self) {
+        if let SyncLocation::Folder { .. } = SyncLocation::parse(self.target.value()) {
+            return;
+        }
+        if let Some(completed) = complete_directory(self.target.value()) {
+            self.target = InputState::new(completed);
+        }
+    }
+
+    fn get_current_input(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.target)
+    }
+}
+
+impl WithPopup for CompareFolder {
+    fn set_popup_state(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    fn get_popup_state(&self) -> bool {
+        self.open
+    }
+}
+
+impl WithContainer<'_> for CompareFolder {}
+
+impl ExecuteEventListener for CompareFolder {
+    fn get_event_listeners(&self) -> &Vec<EventListeners<Self>> {
+        &self.listeners
+    }
+}
+
+impl Component for CompareFolder {
+    fn render(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        _area: ratatui::prelude::Rect,
+        props: Option<ComponentProps>,
+    ) {
+        let folder = match self.prefix.as_str() {
+            "" => "/",
+            prefix => prefix,
+        };
+        let title = format!("Compare {folder} with");
+        let container = self.with_container(&title, &props);
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Max(5), Constraint::Fill(1)])
+            .split(f.size());
+        let center_section = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+            ])
+            .split(layout[1])[1];
+        let target_section = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3)])
+            .margin(1)
+            .split(center_section)[0];
+
+        let target = InputBlock::from_state(
+            &self.target,
+            "Local directory or s3://bucket/prefix".to_string(),
+            true,
+        );
+        f.render_widget(Clear, center_section);
+        f.render_widget(container, center_section);
+        f.render_widget(target, target_section);
+    }
+    fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
+        self.execute(key)
+    }
+    fn handle_paste_events(&mut self, text: &str) {
+        self.execute_paste(text);
+    }
+}
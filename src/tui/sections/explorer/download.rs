@@ -2,17 +2,24 @@ use std::ops::Add;
 
 use crossterm::event::KeyModifiers;
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Margin},
-    widgets::{block::Title, Clear},
+    layout::{Constraint, Direction, Layout},
+    widgets::{block::Title, Clear, Paragraph},
 };
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     action::Action,
-    store::explorer::TreeItem,
+    store::{
+        archives::{ArchiveFormat, ArchiveSettings},
+        conflicts::ConflictPolicy,
+        explorer::TreeItem,
+        filters::KeyFilters,
+        sources::traits::{DownloadPreview, DownloadSettings},
+    },
     tui::{
         components::{
-            input::Input,
+            functions::{complete_directory, format_bytes},
+            input::{InputBlock, InputState},
             popup::WithPopup,
             traits::{Component, ComponentProps, WithContainer},
         },
@@ -20,31 +27,91 @@ use crate::{
     },
 };
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Selected {
+    Destination,
+    Name,
+    Include,
+    Exclude,
+}
+
 #[derive(Debug)]
 pub struct Download {
     pub open: bool,
     pub items: Vec<TreeItem>,
+    names: Vec<InputState>,
+    destination: InputState,
+    // last destination used with the active source
+    default_destination: String,
+    selected: Selected,
     ui_tx: UnboundedSender<Action>,
     current_file_idx: usize,
+    conflict_policy: ConflictPolicy,
+    // policy from the preferences, every download starts with it
+    default_conflict_policy: ConflictPolicy,
+    // the items are bundled in a single archive when set
+    archive_format: Option<ArchiveFormat>,
+    archive_name: InputState,
+    // patterns applied to the content of the selected folders
+    include: InputState,
+    exclude: InputState,
+    preview: Option<DownloadPreview>,
     listeners: Vec<EventListeners<Self>>,
 }
 
 impl Download {
-    pub fn new(ui_tx: UnboundedSender<Action>) -> Download {
+    pub fn new(ui_tx: UnboundedSender<Action>, conflict_policy: ConflictPolicy) -> Download {
         Download {
             ui_tx,
             open: false,
             items: vec![],
+            names: vec![],
+            destination: InputState::default(),
+            default_destination: String::new(),
+            selected: Selected::Name,
             current_file_idx: 0,
+            conflict_policy,
+            default_conflict_policy: conflict_policy,
+            archive_format: None,
+            archive_name: InputState::default(),
+            include: InputState::default(),
+            exclude: InputState::default(),
+            preview: None,
             listeners: Self::register_listeners(),
         }
     }
 
     pub fn init(&mut self, tree_items: Vec<TreeItem>) {
+        self.names = tree_items
+            .iter()
+            .map(|item| InputState::new(item.name().to_string()))
+            .collect();
+        self.archive_name = match tree_items.as_slice() {
+            [item] => InputState::new(item.name().to_string()),
+            _ => InputState::new("download".to_string()),
+        };
+        self.archive_format = None;
+        self.include = InputState::default();
+        self.exclude = InputState::default();
+        if !DownloadPreview::selected_folders(&tree_items).is_empty() {
+            let _ = self.ui_tx.send(Action::PreviewDownload(tree_items.clone()));
+        }
         self.items = tree_items;
+        self.destination = InputState::new(self.default_destination.clone());
+        self.selected = Selected::Name;
+        self.current_file_idx = 0;
+        self.conflict_policy = self.default_conflict_policy;
         self.open = true;
     }
 
+    pub fn set_default_destination(&mut self, destination: String) {
+        self.default_destination = destination;
+    }
+
+    pub fn set_preview(&mut self, preview: Option<DownloadPreview>) {
+        self.preview = preview;
+    }
+
     fn register_listeners() -> Vec<EventListeners<Self>> {
         vec![
             EventListeners::KeyEvent((
@@ -64,15 +131,39 @@ impl Download {
             EventListeners::KeyEvent((
                 S3liKeyEvent::new(
                     vec![(crossterm::event::KeyCode::Tab, KeyModifiers::NONE)],
-                    "Cycle: <Tab>".into(),
+                    "Complete path or cycle: <Tab>".into(),
+                ),
+                Self::complete_or_cycle,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Up, KeyModifiers::NONE)],
+                    "Previous field: <Up>".into(),
+                ),
+                Self::select_previous,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Down, KeyModifiers::NONE)],
+                    "Next field: <Down>".into(),
+                ),
+                Self::select_next,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('p'), KeyModifiers::CONTROL)],
+                    "If the file exists: <C>-p".into(),
                 ),
-                Self::cycle_current_file,
+                Self::cycle_conflict_policy,
             )),
-            EventListeners::OnChangeEvent((
-                S3liOnChangeEvent::new(),
-                Self::add_char,
-                Self::delete_char,
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('a'), KeyModifiers::CONTROL)],
+                    "Archive: <C>-a".into(),
+                ),
+                Self::cycle_archive_format,
             )),
+            EventListeners::OnChangeEvent((S3liOnChangeEvent::new(), Self::get_current_input)),
         ]
     }
 
@@ -80,8 +171,32 @@ impl Download {
         self.open = false;
     }
     fn confirm(&mut self) {
-        // send region to state with ui_tx
-        let _ = self.ui_tx.send(Action::Download(self.items.clone()));
+        // invalid patterns are reported in the popup, which stays open
+        let Ok(filters) = self.filters() else {
+            return;
+        };
+        self.items
+            .iter_mut()
+            .zip(self.names.iter())
+            .for_each(|(item, name)| item.set_name(name.value().to_string()));
+        let settings = DownloadSettings {
+            destination: self.destination.value().trim().to_string(),
+            conflict_policy: self.conflict_policy,
+            archive: self.archive_format.map(|format| {
+                let name = match self.archive_name.value().trim() {
+                    "" => "download",
+                    name => name,
+                };
+                ArchiveSettings {
+                    format,
+                    name: format.with_extension(name),
+                }
+            }),
+            filters,
+        };
+        let _ = self
+            .ui_tx
+            .send(Action::Download(self.items.clone(), settings));
         self.open = false;
     }
     fn cycle_current_file(&mut self) {
@@ -91,14 +206,92 @@ impl Download {
             self.current_file_idx.add(1)
         };
     }
-    fn delete_char(&mut self) {
-        if let Some(item) = self.items.get_mut(self.current_file_idx) {
-            item.pop_name_char();
+    fn complete_or_cycle(&mut self) {
+        match self.selected {
+            Selected::Destination => {
+                if let Some(completed) = complete_directory(self.destination.value()) {
+                    self.destination = InputState::new(completed);
+                }
+            }
+            Selected::Name if self.archive_format.is_none() => self.cycle_current_file(),
+            Selected::Name | Selected::Include | Selected::Exclude => {}
+        }
+    }
+    fn has_folders(&self) -> bool {
+        !DownloadPreview::selected_folders(&self.items).is_empty()
+    }
+    // filters are only offered when folders are selected
+    fn fields(&self) -> Vec<Selected> {
+        if self.has_folders() {
+            vec![
+                Selected::Destination,
+                Selected::Name,
+                Selected::Include,
+                Selected::Exclude,
+            ]
+        } else {
+            vec![Selected::Destination, Selected::Name]
+        }
+    }
+    fn select_next(&mut self) {
+        let fields = self.fields();
+        let idx = fields
+            .iter()
+            .position(|field| *field == self.selected)
+            .unwrap_or_default();
+        self.selected = fields[(idx + 1) % fields.len()];
+    }
+    fn select_previous(&mut self) {
+        let fields = self.fields();
+        let idx = fields
+            .iter()
+            .position(|field| *field == self.selected)
+            .unwrap_or_default();
+        self.selected = fields[(idx + fields.len() - 1) % fields.len()];
+    }
+    fn filters(&self) -> anyhow::Result<KeyFilters> {
+        KeyFilters::parse(self.include.value(), self.exclude.value())
+    }
+    fn preview_text(&self) -> String {
+        let filters = match self.filters() {
+            Ok(filters) => filters,
+            Err(e) => return format!("{e:#}"),
+        };
+        let preview = self
+            .preview
+            .as_ref()
+            .filter(|preview| preview.folders == DownloadPreview::selected_folders(&self.items));
+        let Some(preview) = preview else {
+            return "Listing the selected folders...".to_string();
+        };
+        let mut text = match preview.matching(&filters) {
+            Some((count, bytes, total)) => {
+                format!("{count} of {total} objects match, {}", format_bytes(bytes))
+            }
+            None => format!(
+                "Failed to list the selected folders: {}",
+                preview.objects.as_ref().err().cloned().unwrap_or_default()
+            ),
+        };
+        let files = self.items.len() - preview.folders.len();
+        if files > 0 {
+            text.push_str(&format!(", plus {files} selected files"));
         }
+        text
+    }
+    fn cycle_conflict_policy(&mut self) {
+        self.conflict_policy = self.conflict_policy.next();
     }
-    fn add_char(&mut self, value: char) {
-        if let Some(item) = self.items.get_mut(self.current_file_idx) {
-            item.push_name_char(value);
+    fn cycle_archive_format(&mut self) {
+        self.archive_format = ArchiveFormat::next(self.archive_format);
+    }
+    fn get_current_input(&mut self) -> Option<&mut InputState> {
+        match self.selected {
+            Selected::Destination => Some(&mut self.destination),
+            Selected::Name if self.archive_format.is_some() => Some(&mut self.archive_name),
+            Selected::Name => self.names.get_mut(self.current_file_idx),
+            Selected::Include => Some(&mut self.include),
+            Selected::Exclude => Some(&mut self.exclude),
         }
     }
 }
@@ -129,21 +322,32 @@ impl Component for Download {
         props: Option<ComponentProps>,
     ) {
         let mut container = self.with_container("Download file", &props);
+        let position = match self.archive_format {
+            Some(_) => format!("{} items", self.items.len()),
+            None => format!("{} of {}", self.current_file_idx + 1, self.items.len()),
+        };
+        container = container.title(
+            Title::from(position)
+                .position(ratatui::widgets::block::Position::Bottom)
+                .alignment(ratatui::layout::Alignment::Right),
+        );
         container = container.title(
-            Title::from(format!(
-                "{} of {}",
-                self.current_file_idx + 1,
-                self.items.len()
-            ))
-            .position(ratatui::widgets::block::Position::Bottom)
-            .alignment(ratatui::layout::Alignment::Right),
+            Title::from(format!("if it exists: {}", self.conflict_policy.label()))
+                .position(ratatui::widgets::block::Position::Bottom)
+                .alignment(ratatui::layout::Alignment::Left),
         );
 
-        let current_item = self.items.get(self.current_file_idx);
+        let current_item = self.names.get(self.current_file_idx);
         if let Some(item) = current_item {
+            let has_folders = self.has_folders();
+            let height = if has_folders { 15 } else { 8 };
             let layout = Layout::default()
                 .direction(Direction::Vertical)
-                .constraints([Constraint::Fill(1), Constraint::Max(3), Constraint::Fill(1)])
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Max(height),
+                    Constraint::Fill(1),
+                ])
                 .split(f.size());
             let center_section = Layout::default()
                 .direction(Direction::Horizontal)
@@ -154,13 +358,63 @@ impl Component for Download {
                 ])
                 .split(layout[1])[1];
 
-            let input = Input::new(item.name().to_string(), true);
+            let [destination_section, name_section, include_section, exclude_section, preview_section] =
+                *Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Length(3),
+                        Constraint::Length(3),
+                        Constraint::Length(3),
+                        Constraint::Length(3),
+                        Constraint::Length(1),
+                    ])
+                    .margin(1)
+                    .split(center_section)
+            else {
+                panic!("layout needs to have 5 chunks")
+            };
+            let destination = InputBlock::from_state(
+                &self.destination,
+                "Destination (empty for the working directory)".to_string(),
+                matches!(self.selected, Selected::Destination),
+            );
+            let name = match self.archive_format {
+                Some(format) => InputBlock::from_state(
+                    &self.archive_name,
+                    format!("Archive name ({})", format.extension()),
+                    matches!(self.selected, Selected::Name),
+                ),
+                None => InputBlock::from_state(
+                    item,
+                    "Name".to_string(),
+                    matches!(self.selected, Selected::Name),
+                ),
+            };
             f.render_widget(Clear, center_section);
             f.render_widget(container, center_section);
-            f.render_widget(input, center_section.inner(&Margin::new(1, 1)));
+            f.render_widget(destination, destination_section);
+            f.render_widget(name, name_section);
+            if has_folders {
+                let include = InputBlock::from_state(
+                    &self.include,
+                    "Include (globs, re: for regexes)".to_string(),
+                    matches!(self.selected, Selected::Include),
+                );
+                let exclude = InputBlock::from_state(
+                    &self.exclude,
+                    "Exclude".to_string(),
+                    matches!(self.selected, Selected::Exclude),
+                );
+                f.render_widget(include, include_section);
+                f.render_widget(exclude, exclude_section);
+                f.render_widget(Paragraph::new(self.preview_text()), preview_section);
+            }
         }
     }
     fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
         self.execute(key)
     }
+    fn handle_paste_events(&mut self, text: &str) {
+        self.execute_paste(text);
+    }
 }
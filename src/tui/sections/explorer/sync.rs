@@ -0,0 +1,359 @@
+use crossterm::event::KeyModifiers;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    widgets::{block::Title, Clear, Paragraph},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::Action,
+    store::sync::{SyncDirection, SyncLocation, SyncPreview, SyncRequest},
+    tui::{
+        components::{
+            functions::complete_directory,
+            input::{InputBlock, InputState},
+            popup::WithPopup,
+            traits::{Component, ComponentProps, WithContainer},
+        },
+        key_event::{EventListeners, ExecuteEventListener, S3liKeyEvent, S3liOnChangeEvent},
+    },
+};
+
+// compares a local directory or another folder with the selected one, the planned actions are listed and
+// only run once confirmed
+#[derive(Debug)]
+pub struct SyncFolder {
+    pub open: bool,
+    // key of the folder, empty for the root of the bucket
+    prefix: String,
+    source: Option<String>,
+    target: InputState,
+    // last destination used with the active source
+    default_target: String,
+    direction: SyncDirection,
+    delete: bool,
+    // request whose plan is being computed or shown
+    requested: Option<SyncRequest>,
+    preview: Option<SyncPreview>,
+    scroll: u16,
+    ui_tx: UnboundedSender<Action>,
+    listeners: Vec<EventListeners<Self>>,
+}
+
+impl SyncFolder {
+    pub fn new(ui_tx: UnboundedSender<Action>) -> Self {
+        Self {
+            open: false,
+            prefix: String::new(),
+            source: None,
+            target: InputState::default(),
+            default_target: String::new(),
+            direction: SyncDirection::default(),
+            delete: false,
+            requested: None,
+            preview: None,
+            scroll: 0,
+            ui_tx,
+            listeners: Self::register_listeners(),
+        }
+    }
+
+    pub fn init(&mut self, prefix: String) {
+        self.prefix = prefix;
+        self.target = InputState::new(self.default_target.clone());
+        self.direction = SyncDirection::default();
+        self.delete = false;
+        self.requested = None;
+        self.scroll = 0;
+        self.open = true;
+    }
+
+    pub fn set_default_target(&mut self, target: String) {
+        self.default_target = target;
+    }
+
+    pub fn set_source(&mut self, source: Option<String>) {
+        self.source = source;
+    }
+
+    pub fn set_preview(&mut self, preview: Option<SyncPreview>) {
+        self.preview = preview;
+    }
+
+    fn register_listeners() -> Vec<EventListeners<Self>> {
+        vec![
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Esc, KeyModifiers::NONE)],
+                    "Cancel: <Esc>".into(),
+                ),
+                Self::exit_component,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Enter, KeyModifiers::NONE)],
+                    "Compare, then sync: <Enter>".into(),
+                ),
+                Self::confirm,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Tab, KeyModifiers::NONE)],
+                    "Complete path: <Tab>".into(),
+                ),
+                Self::complete_target,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('d'), KeyModifiers::CONTROL)],
+                    "Direction: <C>-d".into(),
+                ),
+                Self::toggle_direction,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Char('x'), KeyModifiers::CONTROL)],
+                    "Delete extra files: <C>-x".into(),
+                ),
+                Self::toggle_delete,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Up, KeyModifiers::NONE)],
+                    "Scroll up: <Up>".into(),
+                ),
+                Self::scroll_up,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(crossterm::event::KeyCode::Down, KeyModifiers::NONE)],
+                    "Scroll down: <Down>".into(),
+                ),
+                Self::scroll_down,
+            )),
+            EventListeners::OnChangeEvent((S3liOnChangeEvent::new(), Self::get_current_input)),
+        ]
+    }
+
+    fn exit_component(&mut self) {
+        self.open = false;
+    }
+
+    fn request(&self) -> Option<SyncRequest> {
+        Some(SyncRequest {
+            source: self.source.clone()?,
+            prefix: self.prefix.clone(),
+            target: SyncLocation::parse(self.target.value()),
+            direction: self.direction,
+            delete: self.delete,
+        })
+    }
+
+    // preview of the request as currently set in the popup, if it has been planned
+    fn current_preview(&self) -> Option<&SyncPreview> {
+        let request = self.request()?;
+        if self.requested.as_ref() != Some(&request) {
+            return None;
+        }
+        self.preview
+            .as_ref()
+            .filter(|preview| preview.request == request)
+    }
+
+    // the first confirmation compares both sides, the second one runs the plan.
+    // any change made in between asks for a new comparison
+    fn confirm(&mut self) {
+        if let Some(plan) = self
+            .current_preview()
+            .and_then(|preview| preview.plan.clone().ok())
+        {
+            if !plan.actions.is_empty() {
+                let _ = self.ui_tx.send(Action::RunSync(plan));
+            }
+            self.open = false;
+            return;
+        }
+        let Some(request) = self.request() else {
+            return;
+        };
+        // still comparing, a failed comparison can be started again
+        let comparing =
+            self.requested.as_ref() == Some(&request) && self.current_preview().is_none();
+        if self.target.value().trim().is_empty() || comparing {
+            return;
+        }
+        self.scroll = 0;
+        self.requested = Some(request.clone());
+        let _ = self.ui_tx.send(Action::PlanSync(request));
+    }
+
+    // only local directories are completed
+    fn complete_target(&mut self) {
+        if let SyncLocation::Folder { .. } = SyncLocation::parse(self.target.value()) {
+            return;
+        }
+        if let Some(completed) = complete_directory(self.target.value()) {
+            self.target = InputState::new(completed);
+        }
+    }
+
+    fn toggle_direction(&mut self) {
+        self.direction = self.direction.toggle();
+    }
+
+    fn toggle_delete(&mut self) {
+        self.delete = !self.delete;
+    }
+
+    fn scroll_up(&mut self) {
+        self.scroll = self.scroll.saturating_sub(1);
+    }
+
+    fn scroll_down(&mut self) {
+        self.scroll = self.scroll.saturating_add(1);
+    }
+
+    fn get_current_input(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.target)
+    }
+
+    fn plan_text(&self) -> (String, String) {
+        if self.requested.is_none() || self.request() != self.requested {
+            return (
+                "Press <Enter> to compare without changing anything".to_string(),
+                String::new(),
+            );
+        }
+        let Some(preview) = self.current_preview() else {
+            return ("Comparing...".to_string(), String::new());
+        };
+        match &preview.plan {
+            Ok(plan) if plan.actions.is_empty() && plan.rejected.is_empty() => (
+                format!("Already in sync, {}", plan.summary()),
+                String::new(),
+            ),
+            Ok(plan) => {
+                let mut lines = plan
+                    .actions
+                    .iter()
+                    .map(|action| action.describe())
+                    .collect::<Vec<_>>();
+                lines.extend(
+                    plan.rejected
+                        .iter()
+                        .map(|(key, reason)| format!("ignored  {key}: {reason}")),
+                );
+                let summary = if plan.actions.is_empty() {
+                    plan.summary()
+                } else {
+                    format!("{}, <Enter> to sync", plan.summary())
+                };
+                (summary, lines.join("\n"))
+            }
+            Err(e) => (format!("Failed to compare: {e}"), String::new()),
+        }
+    }
+}
+
+impl WithPopup for SyncFolder {
+    fn set_popup_state(&mut self, open: bool) {
+        self.open = open;
+    }
+
+    fn get_popup_state(&self) -> bool {
+        self.open
+    }
+}
+
+impl WithContainer<'_> for SyncFolder {}
+
+impl ExecuteEventListener for SyncFolder {
+    fn get_event_listeners(&self) -> &Vec<EventListeners<Self>> {
+        &self.listeners
+    }
+}
+
+impl Component for SyncFolder {
+    fn render(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        _area: ratatui::prelude::Rect,
+        props: Option<ComponentProps>,
+    ) {
+        let folder = match self.prefix.as_str() {
+            "" => "/",
+            prefix => prefix,
+        };
+        let title = format!("Sync {folder}");
+        let mut container = self.with_container(&title, &props);
+        container = container.title(
+            Title::from(
+                self.direction
+                    .label(&SyncLocation::parse(self.target.value())),
+            )
+            .position(ratatui::widgets::block::Position::Bottom)
+            .alignment(ratatui::layout::Alignment::Left),
+        );
+        container = container.title(
+            Title::from(if self.delete {
+                "delete extra files"
+            } else {
+                "keep extra files"
+            })
+            .position(ratatui::widgets::block::Position::Bottom)
+            .alignment(ratatui::layout::Alignment::Right),
+        );
+
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Max(20),
+                Constraint::Fill(1),
+            ])
+            .split(f.size());
+        let center_section = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Fill(1),
+                Constraint::Fill(4),
+                Constraint::Fill(1),
+            ])
+            .split(layout[1])[1];
+        let [target_section, summary_section, actions_section] = *Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ])
+            .margin(1)
+            .split(center_section)
+        else {
+            panic!("layout needs to have 3 chunks")
+        };
+
+        let target = InputBlock::from_state(
+            &self.target,
+            "Local directory or s3://bucket/prefix".to_string(),
+            true,
+        );
+        let (summary, actions) = self.plan_text();
+        let max_scroll = actions.lines().count().saturating_sub(1) as u16;
+        self.scroll = self.scroll.min(max_scroll);
+        f.render_widget(Clear, center_section);
+        f.render_widget(container, center_section);
+        f.render_widget(target, target_section);
+        f.render_widget(Paragraph::new(summary), summary_section);
+        f.render_widget(
+            Paragraph::new(actions).scroll((self.scroll, 0)),
+            actions_section,
+        );
+    }
+    fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
+        self.execute(key)
+    }
+    fn handle_paste_events(&mut self, text: &str) {
+        self.execute_paste(text);
+    }
+}
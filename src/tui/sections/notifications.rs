@@ -1,15 +1,20 @@
+use crossterm::event::KeyCode;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Style},
-    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+    text::Span,
+    widgets::{block::Title, Block, BorderType, Borders, Clear, Paragraph, Wrap},
 };
 use tokio::sync::mpsc::UnboundedSender;
 
 use crate::{
     action::Action,
-    store::notifications::{
-        types::{Notification, NotificationType},
-        Notifications,
+    store::{
+        notifications::{
+            types::{Notification, NotificationType},
+            Notifications,
+        },
+        tasks::Task,
     },
     tui::components::traits::{Component, ComponentProps},
 };
@@ -17,6 +22,7 @@ use crate::{
 pub struct NotificationsUI {
     notifications: Notifications,
     alert: Option<Notification>,
+    tasks: Vec<Task>,
     ui_tx: UnboundedSender<Action>,
 }
 
@@ -26,6 +32,7 @@ impl NotificationsUI {
             notifications,
             ui_tx,
             alert: None,
+            tasks: vec![],
         }
     }
     pub fn refresh(&mut self, notifications: Notifications) {
@@ -38,6 +45,26 @@ impl NotificationsUI {
     pub fn set_alert(&mut self, alert: Option<Notification>) {
         self.alert = alert;
     }
+
+    pub fn add_task(&mut self, task: Task) {
+        self.tasks.push(task);
+    }
+
+    pub fn remove_task(&mut self, task_id: usize) {
+        self.tasks.retain(|task| task.id != task_id);
+    }
+
+    fn running_tasks_title(&self) -> Option<String> {
+        match &self.tasks[..] {
+            [] => None,
+            [task] => Some(format!("Running: {}", task.description)),
+            [task, others @ ..] => Some(format!(
+                "Running: {} (+{} more)",
+                task.description,
+                others.len()
+            )),
+        }
+    }
 }
 
 impl Component for NotificationsUI {
@@ -70,7 +97,11 @@ impl Component for NotificationsUI {
                 .margin(1)
                 .split(layout[1])[1];
 
-            let notification_text = Paragraph::new(alert.get_message())
+            let mut message = alert.get_message().to_string();
+            if alert.get_retry().is_some() {
+                message.push_str("\n\nPress r to retry the failed items, any other key to close");
+            }
+            let notification_text = Paragraph::new(message)
                 .block(container.clone())
                 .wrap(Wrap::default())
                 .style(Style::default().fg(Color::Red));
@@ -87,9 +118,25 @@ impl Component for NotificationsUI {
             let notification_text = Paragraph::new(notification.get_message()).style(style);
             f.render_widget(notification_text, inner_container);
         };
+        let container = match self.running_tasks_title() {
+            Some(running_tasks) => container.title(
+                Title::from(Span::styled(
+                    running_tasks,
+                    Style::default().fg(Color::Yellow),
+                ))
+                .alignment(Alignment::Right),
+            ),
+            None => container,
+        };
         f.render_widget(container, area);
     }
-    fn handle_key_events(&mut self, _key: crossterm::event::KeyEvent) {
+    fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
+        if let (KeyCode::Char('r'), Some(retry)) = (
+            key.code,
+            self.alert.as_ref().and_then(|alert| alert.get_retry()),
+        ) {
+            let _ = self.ui_tx.send(Action::RetryDownloads(retry.clone()));
+        }
         self.alert = None;
         let _ = self.ui_tx.send(Action::DismissLastAlert);
     }
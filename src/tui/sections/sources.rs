@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crossterm::event::KeyModifiers;
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -20,15 +22,20 @@ pub struct Sources {
 
 impl Sources {
     pub fn new(
-        items: &Vec<String>,
+        items: &[String],
         active_source: &Option<String>,
+        sources_regions: &HashMap<String, String>,
         ui_tx: UnboundedSender<Action>,
     ) -> Sources {
+        let with_region = |source: &String| match sources_regions.get(source) {
+            Some(region) => format!("{source} ({region})"),
+            None => source.to_string(),
+        };
         Sources {
             component: ListComponent::new(
                 "Sources".to_string(),
-                items.to_owned(),
-                active_source.to_owned(),
+                items.iter().map(with_region).collect(),
+                active_source.as_ref().map(with_region),
             ),
             ui_tx,
             listeners: Self::register_listeners(),
@@ -0,0 +1,297 @@
+use std::time::Instant;
+
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{block::Title, Block, BorderType, Borders, List, ListState},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    action::Action,
+    store::{
+        state::DashboardComponents,
+        transfers::{Transfer, TransferEvent, TransferStatus, Transfers},
+    },
+    tui::{
+        components::{
+            functions::{format_bytes, format_duration},
+            traits::{Component, ComponentProps},
+        },
+        key_event::{EventListeners, ExecuteEventListener, S3liKeyEvent},
+    },
+};
+
+// the panel grows with the transfers up to this amount of rows
+const MAX_VISIBLE_TRANSFERS: u16 = 6;
+
+pub struct TransfersUI {
+    transfers: Transfers,
+    selected_id: Option<usize>,
+    // component to go back to once the panel loses focus
+    previous_component: DashboardComponents,
+    ui_tx: UnboundedSender<Action>,
+    listeners: Vec<EventListeners<Self>>,
+}
+
+impl TransfersUI {
+    pub fn new(ui_tx: UnboundedSender<Action>) -> Self {
+        Self {
+            transfers: Transfers::default(),
+            selected_id: None,
+            previous_component: DashboardComponents::default(),
+            ui_tx,
+            listeners: Self::register_listeners(),
+        }
+    }
+
+    fn register_listeners() -> Vec<EventListeners<Self>> {
+        vec![
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![
+                        (KeyCode::Char('k'), KeyModifiers::NONE),
+                        (KeyCode::Up, KeyModifiers::NONE),
+                    ],
+                    "Move up: k or <Up>".into(),
+                ),
+                Self::move_up,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![
+                        (KeyCode::Char('j'), KeyModifiers::NONE),
+                        (KeyCode::Down, KeyModifiers::NONE),
+                    ],
+                    "Move down: j or <Down>".into(),
+                ),
+                Self::move_down,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(KeyCode::Char('c'), KeyModifiers::NONE)],
+                    "Cancel: c".into(),
+                ),
+                Self::cancel_selected,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![(KeyCode::Char('C'), KeyModifiers::SHIFT)],
+                    "Cancel all: C".into(),
+                ),
+                Self::cancel_all,
+            )),
+            EventListeners::KeyEvent((
+                S3liKeyEvent::new(
+                    vec![
+                        (KeyCode::Esc, KeyModifiers::NONE),
+                        (KeyCode::Char('t'), KeyModifiers::CONTROL),
+                    ],
+                    "Back: <Esc> or <C>-t".into(),
+                ),
+                Self::back,
+            )),
+        ]
+    }
+
+    pub fn focus(&mut self, previous_component: DashboardComponents) {
+        self.previous_component = previous_component;
+        if self.selected_id.is_none() {
+            self.selected_id = self.sorted_transfers().first().map(|transfer| transfer.id);
+        }
+        let _ = self
+            .ui_tx
+            .send(Action::SetSelectedComponent(DashboardComponents::Transfers));
+    }
+
+    fn back(&mut self) {
+        let _ = self.ui_tx.send(Action::SetSelectedComponent(
+            self.previous_component.clone(),
+        ));
+    }
+
+    fn selected_position(&self) -> Option<usize> {
+        let selected_id = self.selected_id?;
+        self.sorted_transfers()
+            .iter()
+            .position(|transfer| transfer.id == selected_id)
+    }
+
+    fn select_position(&mut self, position: usize) {
+        self.selected_id = self
+            .sorted_transfers()
+            .get(position)
+            .map(|transfer| transfer.id);
+    }
+
+    fn move_up(&mut self) {
+        let position = self.selected_position().unwrap_or(0);
+        self.select_position(position.saturating_sub(1));
+    }
+
+    fn move_down(&mut self) {
+        let len = self.transfers.get_transfers().len();
+        let position = self
+            .selected_position()
+            .map(|position| (position + 1).min(len.saturating_sub(1)))
+            .unwrap_or(0);
+        self.select_position(position);
+    }
+
+    fn cancel_selected(&mut self) {
+        let selected = self.selected_id.and_then(|selected_id| {
+            self.transfers
+                .get_transfers()
+                .iter()
+                .find(|transfer| transfer.id == selected_id)
+        });
+        if let Some(transfer) = selected {
+            if matches!(
+                transfer.status,
+                TransferStatus::Queued | TransferStatus::Running
+            ) {
+                let _ = self.ui_tx.send(Action::CancelTransfer(transfer.id));
+            }
+        }
+    }
+
+    fn cancel_all(&mut self) {
+        let _ = self.ui_tx.send(Action::CancelAllTransfers);
+    }
+
+    pub fn get_key_event_descriptions(&self) -> Vec<String> {
+        self.extract_key_event_descriptions()
+    }
+
+    pub fn handle_transfer_event(&mut self, event: TransferEvent) {
+        self.transfers.apply(event);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transfers.is_empty()
+    }
+
+    pub fn height(&self) -> u16 {
+        let rows = self.transfers.get_transfers().len() as u16;
+        rows.min(MAX_VISIBLE_TRANSFERS) + 2
+    }
+
+    fn summary(&self) -> String {
+        let transfers = self.transfers.get_transfers();
+        let completed = transfers
+            .iter()
+            .filter(|transfer| transfer.status == TransferStatus::Completed)
+            .count();
+        let failed = transfers
+            .iter()
+            .filter(|transfer| matches!(transfer.status, TransferStatus::Failed(_)))
+            .count();
+        let mut summary = format!("{completed}/{} completed", transfers.len());
+        if failed > 0 {
+            summary.push_str(&format!(", {failed} failed"));
+        }
+        summary
+    }
+
+    fn transfer_line(transfer: &Transfer, now: Instant) -> Line<'static> {
+        let (status, color) = match &transfer.status {
+            TransferStatus::Queued => ("queued".to_string(), Color::Gray),
+            TransferStatus::Running => ("running".to_string(), Color::Yellow),
+            TransferStatus::Completed => ("done".to_string(), Color::LightGreen),
+            TransferStatus::Failed(_) => ("failed".to_string(), Color::Red),
+            TransferStatus::Cancelled => ("cancelled".to_string(), Color::DarkGray),
+            TransferStatus::Skipped => ("skipped".to_string(), Color::DarkGray),
+        };
+        let bytes = match transfer.total_bytes {
+            Some(total_bytes) => format!(
+                "{} / {}",
+                format_bytes(transfer.bytes_done),
+                format_bytes(total_bytes)
+            ),
+            None => format_bytes(transfer.bytes_done),
+        };
+        let mut details = vec![bytes];
+        if let Some(throughput) = transfer.throughput(now) {
+            details.push(format!("{}/s", format_bytes(throughput as u64)));
+        }
+        if let Some(eta) = transfer.eta(now) {
+            details.push(format!("ETA {}", format_duration(eta)));
+        }
+        if let TransferStatus::Failed(error) = &transfer.status {
+            details.push(error.clone());
+        }
+        Line::from(vec![
+            Span::styled(format!("{status: <10}"), Style::default().fg(color)),
+            Span::raw(format!("{}  ", transfer.name)),
+            Span::styled(details.join("  "), Style::default().fg(Color::Gray)),
+        ])
+    }
+
+    // running transfers come first so they stay visible when the panel is full
+    fn sorted_transfers(&self) -> Vec<&Transfer> {
+        let mut transfers = self.transfers.get_transfers().iter().collect::<Vec<_>>();
+        transfers.sort_by_key(|transfer| match transfer.status {
+            TransferStatus::Running => 0,
+            TransferStatus::Queued => 1,
+            TransferStatus::Failed(_) => 2,
+            TransferStatus::Cancelled | TransferStatus::Skipped => 3,
+            TransferStatus::Completed => 4,
+        });
+        transfers
+    }
+}
+
+impl ExecuteEventListener for TransfersUI {
+    fn get_event_listeners(&self) -> &Vec<EventListeners<Self>> {
+        &self.listeners
+    }
+}
+
+impl Component for TransfersUI {
+    fn render(
+        &mut self,
+        f: &mut ratatui::prelude::Frame,
+        area: ratatui::prelude::Rect,
+        props: Option<ComponentProps>,
+    ) {
+        let is_selected = matches!(props, Some(ComponentProps { selected: true }));
+        let container = Block::default()
+            .borders(Borders::ALL)
+            .title("Transfers")
+            .title(Title::from(self.summary()).alignment(Alignment::Right))
+            .border_type(BorderType::Rounded)
+            .border_style(if is_selected {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default()
+            });
+        let now = Instant::now();
+        // only the visible rows are built, a folder can queue a huge amount of transfers
+        let rows = usize::from(container.inner(area).height).max(1);
+        let selected_position = self.selected_position().filter(|_| is_selected);
+        let offset = selected_position
+            .map(|position| position.saturating_sub(rows - 1))
+            .unwrap_or(0);
+        let lines = self
+            .sorted_transfers()
+            .into_iter()
+            .skip(offset)
+            .take(rows)
+            .map(|transfer| Self::transfer_line(transfer, now))
+            .collect::<Vec<_>>();
+        let mut list_state =
+            ListState::default().with_selected(selected_position.map(|position| position - offset));
+        let list = List::new(lines)
+            .block(container)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, area, &mut list_state);
+    }
+    fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        self.execute(key)
+    }
+}
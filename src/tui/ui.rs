@@ -6,8 +6,8 @@ use std::{
 use anyhow::{Context, Result};
 use crossterm::{
     event::{
-        DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyCode, KeyEvent,
-        KeyModifiers,
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event, EventStream, KeyCode, KeyEvent, KeyModifiers,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -51,16 +51,20 @@ impl Ui {
                     // println!("ticking");
                 },
                 maybe_events = term_events.next() => {
-                    if let Some(Ok(Event::Key(event))) = maybe_events {
-                        if let KeyEvent{
-                                code: KeyCode::Char('q'),
-                                modifiers: KeyModifiers::CONTROL | KeyModifiers::SHIFT,
-                                ..
-                            } = event {
-                            self.tx.send(Action::Quit)?;
-                            break Ok(());
+                    match maybe_events {
+                        Some(Ok(Event::Key(event))) => {
+                            if let KeyEvent{
+                                    code: KeyCode::Char('q'),
+                                    modifiers: KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+                                    ..
+                                } = event {
+                                self.tx.send(Action::Quit)?;
+                                break Ok(());
+                            }
+                            dash.handle_key_events(event);
                         }
-                        dash.handle_key_events(event);
+                        Some(Ok(Event::Paste(text))) => dash.handle_paste_events(&text),
+                        _ => {}
                     }
                 },
                 Some(updated_state) = state_rx.recv() => {
@@ -71,7 +75,18 @@ impl Ui {
                         StateEvents::Alert(alert) => {
                             dash.handle_alert(alert);
                         }
-
+                        StateEvents::TaskStarted(task) => {
+                            dash.handle_task_started(task);
+                        }
+                        StateEvents::TaskFinished(task_id) => {
+                            dash.handle_task_finished(task_id);
+                        }
+                        StateEvents::Transfer(event) => {
+                            dash.handle_transfer_event(event);
+                        }
+                        StateEvents::Conflict(conflict) => {
+                            dash.handle_conflict(conflict);
+                        }
                     }
                 },
             }
@@ -95,7 +110,12 @@ fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
 
     enable_raw_mode()?;
 
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
 
     Ok(Terminal::new(CrosstermBackend::new(stdout))?)
 }
@@ -106,7 +126,8 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
 
     Ok(terminal.show_cursor()?)